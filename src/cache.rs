@@ -0,0 +1,471 @@
+use anyhow::{anyhow, Context, Result};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::env;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+pub const CACHE_DIR_ENV: &str = "SKOP_CACHE_DIR";
+/// Used when `config.toml` sets no `[cache] max_size`.
+pub const DEFAULT_MAX_SIZE: u64 = 2 * 1024 * 1024 * 1024;
+const META_NAME: &str = "meta.json";
+const CHECKOUT_DIR: &str = "checkout";
+const REPOS_DIR: &str = "repos";
+const MARKETPLACES_DIR: &str = "marketplaces";
+
+/// Identifies one checkout: a repository at a ref or commit, possibly unpacked partially.
+#[derive(Clone, Debug, Hash, Eq, PartialEq)]
+pub struct CacheKey {
+    pub repo: String,
+    pub git_ref: Option<String>,
+    pub commit: Option<String>,
+    /// Set for tarballs of which only this subpath was unpacked.
+    pub subpath: Option<String>,
+}
+
+impl CacheKey {
+    /// Keys naming a commit always resolve to the same content.
+    pub fn is_immutable(&self) -> bool {
+        self.commit.is_some()
+    }
+
+    fn dir_name(&self) -> String {
+        digest(&[
+            Some(self.repo.as_str()),
+            self.git_ref.as_deref(),
+            self.commit.as_deref(),
+            self.subpath.as_deref(),
+        ])
+    }
+}
+
+impl fmt::Display for CacheKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (&self.commit, &self.git_ref) {
+            (Some(commit), _) => write!(f, "{} at {}", self.repo, commit),
+            (None, Some(git_ref)) => write!(f, "{} at {}", self.repo, git_ref),
+            (None, None) => write!(f, "{}", self.repo),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EntryKind {
+    Git,
+    Tarball,
+    /// An npm package; the key's commit is the package version.
+    Npm,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct EntryMeta {
+    pub url: String,
+    #[serde(rename = "ref")]
+    pub git_ref: Option<String>,
+    /// Commit the key asked for, as opposed to the one checked out.
+    pub pinned_commit: Option<String>,
+    pub subpath: Option<String>,
+    pub kind: EntryKind,
+    /// Commit currently checked out, if known.
+    pub commit: Option<String>,
+    /// Repository root, relative to the entry directory.
+    pub root: String,
+    pub size: u64,
+    pub fetched_at: u64,
+    pub last_used: u64,
+}
+
+impl EntryMeta {
+    pub fn new(key: &CacheKey, kind: EntryKind) -> Self {
+        let now = now();
+        Self {
+            url: key.repo.clone(),
+            git_ref: key.git_ref.clone(),
+            pinned_commit: key.commit.clone(),
+            subpath: key.subpath.clone(),
+            kind,
+            commit: None,
+            root: CHECKOUT_DIR.to_string(),
+            size: 0,
+            fetched_at: now,
+            last_used: now,
+        }
+    }
+}
+
+/// A cached checkout found on disk.
+pub struct Entry {
+    pub dir: PathBuf,
+    pub meta: EntryMeta,
+}
+
+impl Entry {
+    pub fn root(&self) -> PathBuf {
+        self.dir.join(&self.meta.root)
+    }
+}
+
+/// A checkout being written; it only becomes visible once passed to [`Store::insert`].
+pub struct Staging {
+    dir: tempfile::TempDir,
+}
+
+impl Staging {
+    /// Where the repository contents go.
+    pub fn checkout_dir(&self) -> PathBuf {
+        self.dir.path().join(CHECKOUT_DIR)
+    }
+
+    /// `root` (inside the checkout directory) as stored in [`EntryMeta::root`].
+    pub fn relative_root(&self, root: &Path) -> String {
+        root.strip_prefix(self.dir.path())
+            .unwrap_or(Path::new(CHECKOUT_DIR))
+            .to_string_lossy()
+            .to_string()
+    }
+}
+
+/// Persistent checkouts (`repos/`) and fetched marketplace files (`marketplaces/`)
+/// under the user cache directory, reused across runs.
+pub struct Store {
+    root: PathBuf,
+    // Keeps a fallback directory alive when no cache directory is available.
+    _temp: Option<tempfile::TempDir>,
+}
+
+impl Store {
+    /// `$SKOP_CACHE_DIR`, or the platform cache directory.
+    pub fn open() -> Result<Self> {
+        if let Some(dir) = env::var_os(CACHE_DIR_ENV) {
+            return Self::at(PathBuf::from(dir));
+        }
+        match directories::ProjectDirs::from("", "", "skop") {
+            Some(dirs) => Self::at(dirs.cache_dir().to_path_buf()),
+            None => {
+                log::warn!("No cache directory available; downloads will not be reused.");
+                let temp = tempfile::Builder::new().prefix("skop_cache").tempdir()?;
+                Ok(Self {
+                    root: temp.path().to_path_buf(),
+                    _temp: Some(temp),
+                })
+            }
+        }
+    }
+
+    pub fn at(root: PathBuf) -> Result<Self> {
+        let repos = root.join(REPOS_DIR);
+        fs::create_dir_all(&repos)
+            .with_context(|| format!("Failed to create cache directory {}", repos.display()))?;
+        Ok(Self { root, _temp: None })
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    fn repos(&self) -> PathBuf {
+        self.root.join(REPOS_DIR)
+    }
+
+    pub fn get(&self, key: &CacheKey) -> Option<Entry> {
+        let dir = self.repos().join(key.dir_name());
+        let meta = read_meta(&dir)?;
+        let entry = Entry { dir, meta };
+        entry.root().is_dir().then_some(entry)
+    }
+
+    pub fn stage(&self) -> Result<Staging> {
+        let dir = tempfile::Builder::new()
+            .prefix(".staging")
+            .tempdir_in(self.repos())?;
+        Ok(Staging { dir })
+    }
+
+    /// Moves a finished checkout into place, replacing any previous entry for `key`.
+    pub fn insert(&self, key: &CacheKey, staging: Staging, mut meta: EntryMeta) -> Result<Entry> {
+        meta.size = dir_size(&staging.checkout_dir())?;
+        write_meta(staging.dir.path(), &meta)?;
+        let dir = self.repos().join(key.dir_name());
+        if dir.exists() {
+            fs::remove_dir_all(&dir)
+                .with_context(|| format!("Failed to replace cache entry {}", dir.display()))?;
+        }
+        let staged = staging.dir.keep();
+        fs::rename(&staged, &dir)
+            .with_context(|| format!("Failed to store cache entry {}", dir.display()))?;
+        Ok(Entry { dir, meta })
+    }
+
+    /// Records that an entry was used, or updated in place, now.
+    pub fn touch(&self, entry: &mut Entry, refreshed: bool) -> Result<()> {
+        entry.meta.last_used = now();
+        if refreshed {
+            entry.meta.fetched_at = entry.meta.last_used;
+            entry.meta.size = dir_size(&entry.dir.join(CHECKOUT_DIR))?;
+        }
+        write_meta(&entry.dir, &entry.meta)
+    }
+
+    /// Every entry, least recently used first.
+    pub fn list(&self) -> Result<Vec<Entry>> {
+        let mut entries = Vec::new();
+        for dir_entry in fs::read_dir(self.repos())? {
+            let dir = dir_entry?.path();
+            if let Some(meta) = read_meta(&dir) {
+                entries.push(Entry { dir, meta });
+            }
+        }
+        entries.sort_by_key(|entry| entry.meta.last_used);
+        Ok(entries)
+    }
+
+    pub fn remove(&self, entry: &Entry) -> Result<()> {
+        fs::remove_dir_all(&entry.dir)
+            .with_context(|| format!("Failed to remove cache entry {}", entry.dir.display()))
+    }
+
+    /// Removes every entry, plus staging directories left behind by interrupted
+    /// runs and the saved marketplace files. Returns the number of entries removed.
+    pub fn clean(&self) -> Result<usize> {
+        let mut removed = 0;
+        for dir_entry in fs::read_dir(self.repos())? {
+            let path = dir_entry?.path();
+            if path.is_dir() {
+                fs::remove_dir_all(&path)
+                    .with_context(|| format!("Failed to remove {}", path.display()))?;
+                removed += 1;
+            }
+        }
+        let marketplaces = self.root.join(MARKETPLACES_DIR);
+        if marketplaces.exists() {
+            fs::remove_dir_all(&marketplaces)
+                .with_context(|| format!("Failed to remove {}", marketplaces.display()))?;
+        }
+        Ok(removed)
+    }
+
+    fn marketplace_path(&self, spec: &str) -> PathBuf {
+        self.root
+            .join(MARKETPLACES_DIR)
+            .join(format!("{}.json", digest(&[Some(spec)])))
+    }
+
+    /// Keeps the marketplace.json last fetched for `spec` for offline use.
+    pub fn save_marketplace(&self, spec: &str, content: &str) -> Result<()> {
+        let path = self.marketplace_path(spec);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&path, content).with_context(|| format!("Failed to write {}", path.display()))
+    }
+
+    pub fn load_marketplace(&self, spec: &str) -> Option<String> {
+        fs::read_to_string(self.marketplace_path(spec)).ok()
+    }
+
+    /// Drops entries unused for `max_age_days`, then the least recently used ones
+    /// until the cache fits in `max_size` bytes. Returns the removed entries.
+    pub fn prune(&self, max_size: Option<u64>, max_age_days: Option<u64>) -> Result<Vec<Entry>> {
+        let mut entries = self.list()?;
+        let mut removed = Vec::new();
+        if let Some(days) = max_age_days {
+            let cutoff = now().saturating_sub(days.saturating_mul(86_400));
+            let (old, kept): (Vec<_>, Vec<_>) = entries
+                .into_iter()
+                .partition(|entry| entry.meta.last_used < cutoff);
+            for entry in old {
+                self.remove(&entry)?;
+                removed.push(entry);
+            }
+            entries = kept;
+        }
+        if let Some(max_size) = max_size {
+            let mut total: u64 = entries.iter().map(|entry| entry.meta.size).sum();
+            for entry in entries {
+                if total <= max_size {
+                    break;
+                }
+                total = total.saturating_sub(entry.meta.size);
+                self.remove(&entry)?;
+                removed.push(entry);
+            }
+        }
+        Ok(removed)
+    }
+}
+
+/// Parses sizes like `500M`, `2G`, or `1048576` (bytes).
+pub fn parse_size(value: &str) -> Result<u64> {
+    let trimmed = value.trim();
+    let upper = trimmed.to_ascii_uppercase();
+    let upper = upper
+        .strip_suffix("IB")
+        .or_else(|| upper.strip_suffix('B'))
+        .unwrap_or(&upper);
+    let (number, multiplier) = match upper.chars().last() {
+        Some('K') => (&upper[..upper.len() - 1], 1024),
+        Some('M') => (&upper[..upper.len() - 1], 1024 * 1024),
+        Some('G') => (&upper[..upper.len() - 1], 1024 * 1024 * 1024),
+        _ => (upper, 1),
+    };
+    number
+        .trim()
+        .parse::<u64>()
+        .ok()
+        .and_then(|number| number.checked_mul(multiplier))
+        .ok_or_else(|| anyhow!("Invalid size: {} (expected e.g. 500M or 2G)", trimmed))
+}
+
+pub fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["B", "KiB", "MiB", "GiB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} B", bytes)
+    } else {
+        format!("{:.1} {}", value, UNITS[unit])
+    }
+}
+
+/// Hex of the first 16 bytes of the SHA-256 over `parts`, each terminated by a NUL.
+fn digest(parts: &[Option<&str>]) -> String {
+    let mut hasher = Sha256::new();
+    for part in parts {
+        hasher.update(part.unwrap_or_default().as_bytes());
+        hasher.update([0u8]);
+    }
+    hasher.finalize()[..16]
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+fn read_meta(dir: &Path) -> Option<EntryMeta> {
+    let content = fs::read_to_string(dir.join(META_NAME)).ok()?;
+    serde_json::from_str(&content).ok()
+}
+
+fn write_meta(dir: &Path, meta: &EntryMeta) -> Result<()> {
+    let path = dir.join(META_NAME);
+    fs::write(&path, serde_json::to_string_pretty(meta)?)
+        .with_context(|| format!("Failed to write {}", path.display()))
+}
+
+fn dir_size(dir: &Path) -> Result<u64> {
+    let mut size = 0;
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let file_type = entry.file_type()?;
+        if file_type.is_dir() {
+            size += dir_size(&entry.path())?;
+        } else if file_type.is_file() {
+            size += entry.metadata()?.len();
+        }
+    }
+    Ok(size)
+}
+
+pub fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(repo: &str) -> CacheKey {
+        CacheKey {
+            repo: repo.to_string(),
+            git_ref: None,
+            commit: None,
+            subpath: None,
+        }
+    }
+
+    fn insert(store: &Store, key: &CacheKey, content: &str, last_used: u64) -> Entry {
+        let staging = store.stage().unwrap();
+        fs::create_dir_all(staging.checkout_dir()).unwrap();
+        fs::write(staging.checkout_dir().join("SKILL.md"), content).unwrap();
+        let mut meta = EntryMeta::new(key, EntryKind::Git);
+        meta.last_used = last_used;
+        store.insert(key, staging, meta).unwrap()
+    }
+
+    #[test]
+    fn test_insert_and_get() {
+        let temp = tempfile::tempdir().unwrap();
+        let store = Store::at(temp.path().to_path_buf()).unwrap();
+        let key = key("https://example.com/a.git");
+        assert!(store.get(&key).is_none());
+
+        insert(&store, &key, "first", now());
+        insert(&store, &key, "second", now());
+        let entry = store.get(&key).unwrap();
+        assert_eq!(
+            fs::read_to_string(entry.root().join("SKILL.md")).unwrap(),
+            "second"
+        );
+        assert_eq!(entry.meta.size, "second".len() as u64);
+        assert_eq!(store.list().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_saved_marketplaces_survive_until_clean() {
+        let temp = tempfile::tempdir().unwrap();
+        let store = Store::at(temp.path().to_path_buf()).unwrap();
+        assert!(store.load_marketplace("owner/repo").is_none());
+
+        store.save_marketplace("owner/repo", "{}").unwrap();
+        store.save_marketplace("owner/repo@v1", "{\"v\": 1}").unwrap();
+        insert(&store, &key("https://example.com/a.git"), "skill", now());
+        assert_eq!(store.load_marketplace("owner/repo").as_deref(), Some("{}"));
+        assert_eq!(store.list().unwrap().len(), 1);
+
+        assert_eq!(store.clean().unwrap(), 1);
+        assert!(store.load_marketplace("owner/repo@v1").is_none());
+    }
+
+    #[test]
+    fn test_prune_by_age_then_size() {
+        let temp = tempfile::tempdir().unwrap();
+        let store = Store::at(temp.path().to_path_buf()).unwrap();
+        let day = 24 * 60 * 60;
+        insert(&store, &key("old"), "0123456789", now() - 40 * day);
+        insert(&store, &key("older-used"), "0123456789", now() - 2 * day);
+        insert(&store, &key("recent"), "0123456789", now());
+
+        let removed = store.prune(None, Some(30)).unwrap();
+        assert_eq!(removed.len(), 1);
+        assert_eq!(removed[0].meta.url, "old");
+
+        let removed = store.prune(Some(15), None).unwrap();
+        assert_eq!(removed.len(), 1);
+        assert_eq!(removed[0].meta.url, "older-used");
+        assert!(store.get(&key("recent")).is_some());
+
+        // Older than the epoch keeps everything instead of overflowing.
+        assert!(store.prune(None, Some(u64::MAX)).unwrap().is_empty());
+        assert!(store.get(&key("recent")).is_some());
+    }
+
+    #[test]
+    fn test_parse_size() {
+        assert_eq!(parse_size("1048576").unwrap(), 1024 * 1024);
+        assert_eq!(parse_size("500M").unwrap(), 500 * 1024 * 1024);
+        assert_eq!(parse_size("2GiB").unwrap(), 2 * 1024 * 1024 * 1024);
+        assert_eq!(parse_size("64kb").unwrap(), 64 * 1024);
+        assert!(parse_size("lots").is_err());
+        assert!(parse_size("18446744073709551615G").is_err());
+        assert_eq!(format_size(1536), "1.5 KiB");
+    }
+}
@@ -4,8 +4,20 @@ use std::fmt;
 #[derive(Parser)]
 #[command(name = "skop")]
 #[command(version = "1.0")]
-#[command(about = "Skill Manager for Codex, Opencode, and Antigravity")]
+#[command(about = "Skill Manager for Codex, Opencode, Antigravity, and Claude Code")]
 pub struct Cli {
+    /// How to download plugin sources: tarballs where available, or always git / tarball
+    #[arg(long, global = true, value_enum, default_value_t = FetchMode::Auto)]
+    pub fetch: FetchMode,
+
+    /// Use only marketplaces and plugin sources already in the cache; never touch the network
+    #[arg(long, global = true)]
+    pub offline: bool,
+
+    /// Maximum number of plugin sources to download at once (default: 8)
+    #[arg(long, short = 'j', global = true, value_name = "N", default_value_t = 8)]
+    pub jobs: usize,
+
     #[command(subcommand)]
     pub command: Commands,
 }
@@ -26,30 +38,268 @@ pub enum Commands {
         #[arg(long, default_value_t = 1)]
         max_depth: usize,
 
-        /// Repository owner/name (e.g. owner/repo)
+        /// Skill to install without prompting (repeatable)
+        #[arg(long = "skill", value_name = "NAME")]
+        skills: Vec<String>,
+
+        /// Install every skill found in the marketplace without prompting
+        #[arg(long, conflicts_with = "skills")]
+        all_skills: bool,
+
+        /// Target to install into without prompting (repeatable)
+        #[arg(long = "target", value_name = "TARGET")]
+        targets: Vec<String>,
+
+        /// Skip all prompts (requires --target, and --skill or --all-skills)
+        #[arg(long)]
+        yes: bool,
+
+        /// Record the selection in skop.toml so `skop sync` can reproduce it
+        #[arg(long, conflicts_with = "global")]
+        save: bool,
+
+        /// Install into each agent's per-user skills directory instead of the project
+        #[arg(long)]
+        global: bool,
+
+        /// Branch or tag of the marketplace repo to use (same as owner/repo@ref or URL#ref)
+        #[arg(long = "ref", value_name = "REF")]
+        git_ref: Option<String>,
+
+        /// Fetch every plugin to list its skills, instead of trusting the skills declared in marketplace.json
+        #[arg(long)]
+        scan: bool,
+
+        /// GitHub owner/name, git URL, local directory, or registered marketplace name, optionally pinned to a ref (e.g. owner/repo@v1.0.0)
         repo: String,
     },
-    /// Remove installed skills interactively
-    Remove,
+    /// Remove installed skills (interactive unless skills or --plugin are given)
+    Remove {
+        /// Skill names to remove
+        skills: Vec<String>,
+
+        /// Remove every skill installed by this plugin (repeatable)
+        #[arg(long = "plugin", value_name = "NAME")]
+        plugins: Vec<String>,
+
+        /// Only touch these targets (repeatable)
+        #[arg(long = "target", value_name = "TARGET")]
+        targets: Vec<String>,
+
+        /// Only touch this scope (default: both)
+        #[arg(long, value_enum)]
+        scope: Option<Scope>,
+
+        /// Skip the confirmation prompt
+        #[arg(long)]
+        yes: bool,
+    },
+    /// Install the exact skills pinned in skop.lock
+    Install {
+        /// Show what would be installed without writing files
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Enable verbose logging
+        #[arg(long)]
+        verbose: bool,
+    },
+    /// Converge installed skills to the spec in skop.toml
+    Sync {
+        /// Show what would change without writing files
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Enable verbose logging
+        #[arg(long)]
+        verbose: bool,
+
+        /// Maximum recursion depth when resolving nested marketplaces (default: 1)
+        #[arg(long, default_value_t = 1)]
+        max_depth: usize,
+    },
+    /// Update installed plugins from the marketplaces they came from
+    Update {
+        /// Show what would be updated without writing files
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Enable verbose logging
+        #[arg(long)]
+        verbose: bool,
+
+        /// Maximum recursion depth when resolving nested marketplaces (default: 1)
+        #[arg(long, default_value_t = 1)]
+        max_depth: usize,
+
+        /// Only update these targets (repeatable)
+        #[arg(long = "target", value_name = "TARGET")]
+        targets: Vec<String>,
+
+        /// Only update this scope (default: both)
+        #[arg(long, value_enum)]
+        scope: Option<Scope>,
+
+        /// Plugins to update (default: every installed plugin)
+        plugins: Vec<String>,
+    },
+    /// Report installed plugins that are behind their marketplace (exits non-zero if any)
+    Outdated {
+        /// Enable verbose logging
+        #[arg(long)]
+        verbose: bool,
+
+        /// Only check these targets (repeatable)
+        #[arg(long = "target", value_name = "TARGET")]
+        targets: Vec<String>,
+
+        /// Only check this scope (default: both)
+        #[arg(long, value_enum)]
+        scope: Option<Scope>,
+    },
     /// List installed skills
+    List {
+        /// Only list this scope (default: both)
+        #[arg(long, value_enum)]
+        scope: Option<Scope>,
+
+        /// Also show each skill's description from its SKILL.md
+        #[arg(long)]
+        long: bool,
+    },
+    /// Show where a skill or plugin is installed, where it came from, and whether it was edited
+    Info {
+        /// Enable verbose logging
+        #[arg(long)]
+        verbose: bool,
+
+        /// Skill or plugin name
+        name: String,
+
+        /// Look the name up in this marketplace instead of the installed skills
+        #[arg(long, value_name = "REPO")]
+        marketplace: Option<String>,
+
+        /// Only look in this scope (default: both)
+        #[arg(long, value_enum)]
+        scope: Option<Scope>,
+    },
+    /// Search the registered marketplaces, and those in skop.toml or already installed from, for plugins and skills
+    Search {
+        /// Enable verbose logging
+        #[arg(long)]
+        verbose: bool,
+
+        /// Fetch every plugin to search all skill descriptions, instead of only declared skills and plugins already in the cache
+        #[arg(long)]
+        scan: bool,
+
+        /// Words to look for in plugin and skill names, descriptions, and keywords
+        #[arg(required = true, num_args = 1..)]
+        query: Vec<String>,
+    },
+    /// Check a marketplace working tree for problems before publishing (exits non-zero on errors)
+    Lint {
+        /// Marketplace directory, containing .claude-plugin/marketplace.json
+        #[arg(default_value = ".")]
+        path: String,
+
+        /// Print the report as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Inspect or trim the cache of downloaded plugin sources
+    Cache {
+        #[command(subcommand)]
+        command: CacheCommand,
+    },
+    /// Register marketplaces under a name that `skop add <NAME>` and friends accept
+    Marketplace {
+        #[command(subcommand)]
+        command: MarketplaceCommand,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum MarketplaceCommand {
+    /// Register a marketplace (named after its marketplace.json unless --name is given)
+    Add {
+        /// GitHub owner/name, git URL, or local directory, optionally pinned to a ref
+        repo: String,
+
+        /// Name to register the marketplace under
+        #[arg(long)]
+        name: Option<String>,
+
+        /// Register in the project's .skop/config.toml instead of the user config
+        #[arg(long)]
+        project: bool,
+    },
+    /// List registered marketplaces
+    List,
+    /// Unregister a marketplace
+    Remove {
+        /// Registered name
+        name: String,
+
+        /// Remove from the project's .skop/config.toml instead of the user config
+        #[arg(long)]
+        project: bool,
+    },
+    /// Fetch registered marketplaces again to update their cached marketplace.json
+    Refresh {
+        /// Only refresh these (default: all)
+        names: Vec<String>,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum CacheCommand {
+    /// List cached repositories, least recently used first
     List,
+    /// Remove every cached repository
+    Clean,
+    /// Remove cached repositories that are stale or over the size limit
+    Prune {
+        /// Remove entries not used for this many days (default: 30)
+        #[arg(long, value_name = "DAYS", default_value_t = 30)]
+        older_than: u64,
+
+        /// Then drop least recently used entries until the cache fits, e.g. 500M (default: [cache] max_size, or 2G)
+        #[arg(long, value_name = "SIZE")]
+        max_size: Option<String>,
+    },
 }
 
-#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, ValueEnum, Debug)]
-pub enum Target {
-    Codex,
-    Opencode,
-    Antigravity,
-    All,
+#[derive(Copy, Clone, PartialEq, Eq, ValueEnum, Debug)]
+pub enum FetchMode {
+    /// Tarballs for GitHub and `.tar.gz` sources, git for everything else
+    Auto,
+    Git,
+    Tarball,
+}
+
+/// Where skills are installed: the current project or the user's home directory.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, ValueEnum, Debug)]
+pub enum Scope {
+    Project,
+    Global,
+}
+
+impl Scope {
+    pub fn selected(filter: Option<Scope>) -> Vec<Scope> {
+        match filter {
+            Some(scope) => vec![scope],
+            None => vec![Scope::Project, Scope::Global],
+        }
+    }
 }
 
-impl fmt::Display for Target {
+impl fmt::Display for Scope {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            Target::Codex => write!(f, "codex"),
-            Target::Opencode => write!(f, "opencode"),
-            Target::Antigravity => write!(f, "antigravity"),
-            Target::All => write!(f, "all"),
+            Scope::Project => write!(f, "project"),
+            Scope::Global => write!(f, "global"),
         }
     }
 }
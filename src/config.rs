@@ -0,0 +1,224 @@
+use anyhow::{anyhow, Context, Result};
+use log::warn;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+pub const CONFIG_NAME: &str = "config.toml";
+
+/// Settings read from the user config and the project's `.skop/config.toml`.
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub targets: BTreeMap<String, TargetConfig>,
+    #[serde(default, skip_serializing_if = "CacheConfig::is_empty")]
+    pub cache: CacheConfig,
+    #[serde(default, skip_serializing_if = "NpmConfig::is_empty")]
+    pub npm: NpmConfig,
+    /// Registered marketplaces by name, e.g. `team = "my-org/skills"`.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub marketplaces: BTreeMap<String, String>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TargetConfig {
+    /// Skills directory relative to the project root.
+    pub dir: String,
+    /// Per-user skills directory: `~`, `~/...` (resolved against the home directory), or absolute.
+    #[serde(default)]
+    pub global_dir: Option<String>,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CacheConfig {
+    /// Size the download cache is trimmed to after each run, e.g. `"500M"` or `"2G"`.
+    #[serde(default)]
+    pub max_size: Option<String>,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct NpmConfig {
+    /// Registry npm plugin sources are resolved against (default: registry.npmjs.org).
+    #[serde(default)]
+    pub registry: Option<String>,
+}
+
+impl CacheConfig {
+    fn is_empty(&self) -> bool {
+        self.max_size.is_none()
+    }
+}
+
+impl NpmConfig {
+    fn is_empty(&self) -> bool {
+        self.registry.is_none()
+    }
+}
+
+impl Config {
+    pub fn load(path: &Path) -> Result<Option<Self>> {
+        if !path.exists() {
+            return Ok(None);
+        }
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        let config = toml::from_str(&content)
+            .with_context(|| format!("Failed to parse {}", path.display()))?;
+        Ok(Some(config))
+    }
+
+    /// Loads the user config, then adds the project config's targets and
+    /// marketplaces. A project entry never replaces a user entry of the same
+    /// name, and `[npm]` is only read from the user config.
+    pub fn load_merged() -> Result<Self> {
+        let config = match user_config_path() {
+            Some(path) => Self::load(&path)?.unwrap_or_default(),
+            None => Self::default(),
+        };
+        let project_path = project_config_path();
+        match Self::load(&project_path)? {
+            Some(project) => Ok(config.merge(project, &project_path)),
+            None => Ok(config),
+        }
+    }
+
+    fn merge(mut self, project: Config, project_path: &Path) -> Self {
+        // A cloned repository must not redirect names the user relies on.
+        for (name, target) in project.targets {
+            match self.targets.get(&name) {
+                Some(_) => warn!(
+                    "Ignoring [targets.{}] in {}; the user config defines it",
+                    name,
+                    project_path.display()
+                ),
+                None => {
+                    self.targets.insert(name, target);
+                }
+            }
+        }
+        for (name, repo) in project.marketplaces {
+            match self.marketplaces.get(&name) {
+                Some(user_repo) if *user_repo != repo => warn!(
+                    "Ignoring marketplace {} = {:?} in {}; the user config registers it as {}",
+                    name,
+                    repo,
+                    project_path.display(),
+                    user_repo
+                ),
+                Some(_) => {}
+                None => {
+                    self.marketplaces.insert(name, repo);
+                }
+            }
+        }
+        if project.cache.max_size.is_some() {
+            self.cache.max_size = project.cache.max_size;
+        }
+        // Nor choose where packages are downloaded from.
+        if let Some(registry) = project.npm.registry {
+            warn!(
+                "Ignoring [npm] registry = {:?} in {}; set it in the user config instead",
+                registry,
+                project_path.display()
+            );
+        }
+        self
+    }
+}
+
+/// Sets `[marketplaces] name = repo` in the config file at `path`, or removes the
+/// entry if `repo` is `None`. Comments and the rest of the file stay as written.
+pub fn set_marketplace(path: &Path, name: &str, repo: Option<&str>) -> Result<()> {
+    let content = if path.exists() {
+        fs::read_to_string(path).with_context(|| format!("Failed to read {}", path.display()))?
+    } else {
+        String::new()
+    };
+    let mut document: toml_edit::DocumentMut = content
+        .parse()
+        .with_context(|| format!("Failed to parse {}", path.display()))?;
+    let marketplaces = document
+        .entry("marketplaces")
+        .or_insert(toml_edit::table())
+        .as_table_like_mut()
+        .ok_or_else(|| anyhow!("[marketplaces] in {} is not a table", path.display()))?;
+    match repo {
+        Some(repo) => {
+            marketplaces.insert(name, toml_edit::value(repo));
+        }
+        None => {
+            marketplaces.remove(name);
+        }
+    }
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create {}", parent.display()))?;
+    }
+    fs::write(path, document.to_string())
+        .with_context(|| format!("Failed to write {}", path.display()))
+}
+
+/// `$SKOP_CONFIG_DIR/config.toml`, or `config.toml` in the platform config dir.
+pub fn user_config_path() -> Option<PathBuf> {
+    if let Some(dir) = env::var_os("SKOP_CONFIG_DIR") {
+        return Some(PathBuf::from(dir).join(CONFIG_NAME));
+    }
+    let dirs = directories::ProjectDirs::from("", "", "skop")?;
+    Some(dirs.config_dir().join(CONFIG_NAME))
+}
+
+pub fn project_config_path() -> PathBuf {
+    crate::util::project_root().join(".skop").join(CONFIG_NAME)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_project_entries_do_not_replace_user_entries() {
+        let user: Config = toml::from_str(
+            "[targets.cursor]\ndir = \".cursor/skills\"\n[marketplaces]\nteam = \"my-org/skills\"\n",
+        )
+        .unwrap();
+        let project: Config = toml::from_str(concat!(
+            "[targets.cursor]\ndir = \"../elsewhere\"\n",
+            "[targets.my-agent]\ndir = \".my-agent/skills\"\n",
+            "[marketplaces]\nteam = \"someone/else\"\nextra = \"my-org/extra\"\n",
+            "[npm]\nregistry = \"https://registry.example.com\"\n",
+        ))
+        .unwrap();
+
+        let config = user.merge(project, Path::new(".skop/config.toml"));
+        assert_eq!(config.targets["cursor"].dir, ".cursor/skills");
+        assert_eq!(config.targets["my-agent"].dir, ".my-agent/skills");
+        assert_eq!(config.marketplaces["team"], "my-org/skills");
+        assert_eq!(config.marketplaces["extra"], "my-org/extra");
+        assert!(config.npm.registry.is_none());
+    }
+
+    #[test]
+    fn test_set_marketplace_keeps_the_rest_of_the_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(CONFIG_NAME);
+        let original = "# Agents we use\n[targets.cursor]\ndir = \".cursor/skills\" # project dir\n";
+        fs::write(&path, original).unwrap();
+
+        set_marketplace(&path, "team", Some("my-org/skills")).unwrap();
+        let content = fs::read_to_string(&path).unwrap();
+        assert!(content.starts_with(original), "{}", content);
+        let config = Config::load(&path).unwrap().unwrap();
+        assert_eq!(config.marketplaces["team"], "my-org/skills");
+
+        set_marketplace(&path, "team", None).unwrap();
+        let content = fs::read_to_string(&path).unwrap();
+        assert!(content.starts_with(original), "{}", content);
+        assert!(Config::load(&path).unwrap().unwrap().marketplaces.is_empty());
+    }
+}
@@ -0,0 +1,352 @@
+use crate::model::PluginSource;
+use crate::skill;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // `pad` so that `{:<7}` lines findings up.
+        match self {
+            Severity::Error => f.pad("error"),
+            Severity::Warning => f.pad("warning"),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct Finding {
+    pub severity: Severity,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub plugin: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub skill: Option<String>,
+    pub message: String,
+}
+
+impl fmt::Display for Finding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let subject = match (&self.plugin, &self.skill) {
+            (Some(plugin), Some(skill)) => format!("{}/{}", plugin, skill),
+            (Some(plugin), None) => plugin.clone(),
+            _ => "marketplace".to_string(),
+        };
+        write!(f, "{:<7} [{}] {}", self.severity, subject, self.message)
+    }
+}
+
+/// Result of checking a marketplace working tree.
+#[derive(Debug, Serialize)]
+pub struct Report {
+    pub marketplace: PathBuf,
+    pub plugins: usize,
+    pub skills: usize,
+    /// Plugins fetched from elsewhere, which are not checked.
+    pub remote: Vec<String>,
+    pub findings: Vec<Finding>,
+}
+
+impl Report {
+    pub fn count(&self, severity: Severity) -> usize {
+        self.findings
+            .iter()
+            .filter(|finding| finding.severity == severity)
+            .count()
+    }
+
+    fn push(
+        &mut self,
+        severity: Severity,
+        plugin: Option<&str>,
+        skill: Option<&str>,
+        message: String,
+    ) {
+        self.findings.push(Finding {
+            severity,
+            plugin: plugin.map(|value| value.to_string()),
+            skill: skill.map(|value| value.to_string()),
+            message,
+        });
+    }
+}
+
+/// Checks the marketplace in `root` with the same resolution `skop add` uses.
+pub fn lint_marketplace(root: &Path) -> Report {
+    let mut report = Report {
+        marketplace: root.join(".claude-plugin/marketplace.json"),
+        plugins: 0,
+        skills: 0,
+        remote: Vec::new(),
+        findings: Vec::new(),
+    };
+    let marketplace = match crate::load_marketplace_file(root) {
+        Ok(marketplace) => marketplace,
+        Err(err) => {
+            report.push(Severity::Error, None, None, format!("{:#}", err));
+            return report;
+        }
+    };
+    report.plugins = marketplace.plugins.len();
+    let plugin_root = marketplace
+        .metadata
+        .as_ref()
+        .and_then(|metadata| metadata.plugin_root.as_deref());
+    if let Some(version) = marketplace.metadata.as_ref().and_then(|m| m.version.as_deref())
+        && semver::Version::parse(version).is_err()
+    {
+        report.push(
+            Severity::Warning,
+            None,
+            None,
+            format!("metadata.version {:?} is not a semver version", version),
+        );
+    }
+
+    let spec = root.to_string_lossy().to_string();
+    // Skill name -> plugin that provides it; they share one skills directory once installed.
+    let mut providers: HashMap<String, String> = HashMap::new();
+    for plugin in &marketplace.plugins {
+        let name = Some(plugin.name.as_str());
+        if let Some(version) = &plugin.version
+            && semver::Version::parse(version).is_err()
+        {
+            report.push(
+                Severity::Error,
+                name,
+                None,
+                format!(
+                    "version {:?} is not a semver version, so updates cannot be detected",
+                    version
+                ),
+            );
+        }
+
+        let location = crate::resolve_plugin_url(plugin, &spec, plugin_root);
+        if !matches!(plugin.source, PluginSource::Path(_)) || location.url != spec {
+            report.remote.push(plugin.name.clone());
+            continue;
+        }
+        let subpath = location.subpath.unwrap_or_default();
+        let source_path = root.join(&subpath);
+        if !source_path.exists() {
+            report.push(
+                Severity::Error,
+                name,
+                None,
+                format!("source path {} does not exist", subpath),
+            );
+            continue;
+        }
+
+        let skill_dirs = match crate::find_skill_dirs(&source_path, plugin) {
+            Ok(skill_dirs) => skill_dirs,
+            Err(err) => {
+                report.push(Severity::Error, name, None, format!("{:#}", err));
+                continue;
+            }
+        };
+        if skill_dirs.is_empty() {
+            report.push(
+                Severity::Error,
+                name,
+                None,
+                format!("no skills found in {}", subpath),
+            );
+            continue;
+        }
+
+        let mut seen: HashMap<String, &Path> = HashMap::new();
+        for dir in &skill_dirs {
+            let Some(skill_name) = dir.file_name().and_then(|name| name.to_str()) else {
+                continue;
+            };
+            let relative = dir.strip_prefix(root).unwrap_or(dir);
+            if let Some(first) = seen.get(skill_name) {
+                report.push(
+                    Severity::Error,
+                    name,
+                    Some(skill_name),
+                    format!(
+                        "found at both {} and {}; only the first is installed",
+                        first.display(),
+                        relative.display()
+                    ),
+                );
+                continue;
+            }
+            seen.insert(skill_name.to_string(), relative);
+            report.skills += 1;
+            match providers.get(skill_name) {
+                Some(other) => report.push(
+                    Severity::Error,
+                    name,
+                    Some(skill_name),
+                    format!("plugin {} has a skill with the same name", other),
+                ),
+                None => {
+                    providers.insert(skill_name.to_string(), plugin.name.clone());
+                }
+            }
+            check_frontmatter(&mut report, &plugin.name, skill_name, dir);
+        }
+    }
+    report
+}
+
+fn check_frontmatter(report: &mut Report, plugin: &str, skill_name: &str, dir: &Path) {
+    let (plugin, skill) = (Some(plugin), Some(skill_name));
+    let frontmatter = match skill::read(dir) {
+        Ok(Some(frontmatter)) => frontmatter,
+        Ok(None) => {
+            report.push(
+                Severity::Error,
+                plugin,
+                skill,
+                "SKILL.md has no frontmatter".to_string(),
+            );
+            return;
+        }
+        Err(err) => {
+            report.push(Severity::Error, plugin, skill, format!("{:#}", err));
+            return;
+        }
+    };
+    match frontmatter.name.as_deref() {
+        None => report.push(
+            Severity::Error,
+            plugin,
+            skill,
+            "frontmatter has no name".to_string(),
+        ),
+        Some(name) if name != skill_name => report.push(
+            Severity::Warning,
+            plugin,
+            skill,
+            format!("frontmatter name {:?} differs from the directory name", name),
+        ),
+        Some(_) => {}
+    }
+    if frontmatter.summary().is_none() {
+        report.push(
+            Severity::Error,
+            plugin,
+            skill,
+            "frontmatter has no description".to_string(),
+        );
+    }
+    if let Some(version) = &frontmatter.version
+        && semver::Version::parse(version).is_err()
+    {
+        report.push(
+            Severity::Warning,
+            plugin,
+            skill,
+            format!("frontmatter version {:?} is not a semver version", version),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn write_skill(dir: &Path, content: &str) {
+        fs::create_dir_all(dir).unwrap();
+        fs::write(dir.join(skill::SKILL_FILE), content).unwrap();
+    }
+
+    fn messages(report: &Report, severity: Severity) -> Vec<String> {
+        let mut messages = report
+            .findings
+            .iter()
+            .filter(|finding| finding.severity == severity)
+            .map(|finding| finding.to_string())
+            .collect::<Vec<_>>();
+        // Skills are discovered in directory order.
+        messages.sort();
+        messages
+    }
+
+    #[test]
+    fn test_lint_marketplace_findings() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path();
+        fs::create_dir_all(root.join(".claude-plugin")).unwrap();
+        fs::write(
+            root.join(".claude-plugin/marketplace.json"),
+            r#"{
+  "name": "team",
+  "owner": { "name": "Team" },
+  "metadata": { "pluginRoot": "./plugins" },
+  "plugins": [
+    { "name": "tools", "source": "tools", "version": "1.0.0" },
+    { "name": "extras", "source": "extras", "version": "v2" },
+    { "name": "empty", "source": "empty" },
+    { "name": "missing", "source": "missing" },
+    { "name": "remote", "source": { "source": "github", "repo": "team/remote" } }
+  ]
+}"#,
+        )
+        .unwrap();
+        let plugins = root.join("plugins");
+        write_skill(
+            &plugins.join("tools/skills/lint"),
+            "---\nname: lint\ndescription: Runs linters.\n---\n",
+        );
+        write_skill(
+            &plugins.join("tools/skills/review"),
+            "---\nname: reviewer\ndescription: Reviews.\nversion: one\n---\n",
+        );
+        write_skill(&plugins.join("extras/skills/lint"), "# No frontmatter\n");
+        write_skill(&plugins.join("extras/skills/format"), "---\nname: format\n---\n");
+        fs::create_dir_all(plugins.join("empty")).unwrap();
+
+        let report = lint_marketplace(root);
+
+        assert_eq!(report.plugins, 5);
+        assert_eq!(report.skills, 4);
+        assert_eq!(report.remote, ["remote"]);
+        assert_eq!(
+            messages(&report, Severity::Error),
+            [
+                "error   [empty] no skills found in ./plugins/empty",
+                "error   [extras/format] frontmatter has no description",
+                "error   [extras/lint] SKILL.md has no frontmatter",
+                "error   [extras/lint] plugin tools has a skill with the same name",
+                "error   [extras] version \"v2\" is not a semver version, so updates cannot be detected",
+                "error   [missing] source path ./plugins/missing does not exist",
+            ]
+        );
+        assert_eq!(
+            messages(&report, Severity::Warning),
+            [
+                "warning [tools/review] frontmatter name \"reviewer\" differs from the directory name",
+                "warning [tools/review] frontmatter version \"one\" is not a semver version",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_lint_reports_unparsable_marketplace() {
+        let temp = tempfile::tempdir().unwrap();
+        fs::create_dir_all(temp.path().join(".claude-plugin")).unwrap();
+        fs::write(
+            temp.path().join(".claude-plugin/marketplace.json"),
+            r#"{ "name": "team", "plugins": [] }"#,
+        )
+        .unwrap();
+
+        let report = lint_marketplace(temp.path());
+        assert_eq!(report.count(Severity::Error), 1);
+        assert!(report.findings[0].message.contains("missing field `owner`"));
+    }
+}
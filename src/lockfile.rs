@@ -0,0 +1,271 @@
+use anyhow::{anyhow, Context, Result};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::{Component, Path};
+
+pub const LOCKFILE_NAME: &str = "skop.lock";
+const LOCKFILE_VERSION: u32 = 1;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Lockfile {
+    pub version: u32,
+    #[serde(default)]
+    pub plugins: Vec<LockedPlugin>,
+}
+
+/// A plugin installed into one target, pinned to the commit it was copied from.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct LockedPlugin {
+    pub name: String,
+    pub target: String,
+    pub marketplace: String,
+    pub version: Option<String>,
+    pub url: String,
+    pub subpath: Option<String>,
+    #[serde(rename = "ref")]
+    pub git_ref: Option<String>,
+    pub commit: String,
+    pub skills: Vec<LockedSkill>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct LockedSkill {
+    pub name: String,
+    /// Skill directory relative to the repository root.
+    pub path: String,
+    pub hash: String,
+}
+
+impl Default for Lockfile {
+    fn default() -> Self {
+        Self {
+            version: LOCKFILE_VERSION,
+            plugins: Vec::new(),
+        }
+    }
+}
+
+impl Lockfile {
+    pub fn load(path: &Path) -> Result<Option<Self>> {
+        if !path.exists() {
+            return Ok(None);
+        }
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        let lockfile: Lockfile = serde_json::from_str(&content)
+            .with_context(|| format!("Failed to parse {}", path.display()))?;
+        if lockfile.version != LOCKFILE_VERSION {
+            return Err(anyhow!(
+                "{} has version {}, but this skop only supports version {}",
+                path.display(),
+                lockfile.version,
+                LOCKFILE_VERSION
+            ));
+        }
+        lockfile
+            .check_paths()
+            .with_context(|| format!("Invalid entry in {}", path.display()))?;
+        Ok(Some(lockfile))
+    }
+
+    /// Names become directories under the skills directory and paths are read
+    /// from the checkout, so neither may reach outside of them.
+    fn check_paths(&self) -> Result<()> {
+        for plugin in &self.plugins {
+            if !is_single_name(&plugin.name) {
+                return Err(anyhow!("{:?} is not a valid plugin name", plugin.name));
+            }
+            for skill in &plugin.skills {
+                if !is_single_name(&skill.name) {
+                    return Err(anyhow!(
+                        "{:?} of {} is not a valid skill name",
+                        skill.name,
+                        plugin.name
+                    ));
+                }
+                let inside = Path::new(&skill.path).components().all(|component| {
+                    matches!(component, Component::Normal(_) | Component::CurDir)
+                });
+                if !inside {
+                    return Err(anyhow!(
+                        "Skill {} of {} has path {:?}, which leaves the repository",
+                        skill.name,
+                        plugin.name,
+                        skill.path
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub fn save(&mut self, path: &Path) -> Result<()> {
+        self.plugins
+            .sort_by(|a, b| a.target.cmp(&b.target).then(a.name.cmp(&b.name)));
+        let content = serde_json::to_string_pretty(self)?;
+        fs::write(path, content + "\n")
+            .with_context(|| format!("Failed to write {}", path.display()))
+    }
+
+    pub fn upsert(&mut self, plugin: LockedPlugin) {
+        self.plugins
+            .retain(|entry| !(entry.target == plugin.target && entry.name == plugin.name));
+        self.plugins.push(plugin);
+    }
+
+    /// Drops removed skills from a target, and any plugin left without skills.
+    pub fn remove_skills(&mut self, target: &str, removed: &[String]) {
+        for plugin in self.plugins.iter_mut().filter(|entry| entry.target == target) {
+            plugin.skills.retain(|skill| !removed.contains(&skill.name));
+        }
+        self.plugins.retain(|entry| !entry.skills.is_empty());
+    }
+}
+
+fn is_single_name(name: &str) -> bool {
+    let mut components = Path::new(name).components();
+    matches!(components.next(), Some(Component::Normal(_))) && components.next().is_none()
+}
+
+/// Hashes a skill directory's file names and contents, ignoring `.git`.
+pub fn hash_dir(path: &Path) -> Result<String> {
+    let mut hasher = Sha256::new();
+    hash_dir_into(path, path, &mut hasher)?;
+    let digest = hasher.finalize();
+    let hex: String = digest.iter().map(|byte| format!("{:02x}", byte)).collect();
+    Ok(format!("sha256:{}", hex))
+}
+
+fn hash_dir_into(root: &Path, dir: &Path, hasher: &mut Sha256) -> Result<()> {
+    let mut entries = fs::read_dir(dir)
+        .with_context(|| format!("Failed to read {}", dir.display()))?
+        .collect::<std::io::Result<Vec<_>>>()?;
+    entries.sort_by_key(|entry| entry.file_name());
+    for entry in entries {
+        let path = entry.path();
+        if entry.file_type()?.is_dir() {
+            if entry.file_name() == ".git" {
+                continue;
+            }
+            hash_dir_into(root, &path, hasher)?;
+            continue;
+        }
+        let relative = path.strip_prefix(root).unwrap_or(&path);
+        let relative = relative
+            .components()
+            .map(|component| component.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+        let content = fs::read(&path)?;
+        hasher.update(relative.as_bytes());
+        hasher.update([0u8]);
+        hasher.update((content.len() as u64).to_le_bytes());
+        hasher.update(&content);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn locked_plugin(name: &str, target: &str, skills: &[&str]) -> LockedPlugin {
+        LockedPlugin {
+            name: name.to_string(),
+            target: target.to_string(),
+            marketplace: "owner/marketplace".to_string(),
+            version: Some("1.0.0".to_string()),
+            url: "https://github.com/owner/marketplace.git".to_string(),
+            subpath: None,
+            git_ref: None,
+            commit: "0123456789abcdef0123456789abcdef01234567".to_string(),
+            skills: skills
+                .iter()
+                .map(|skill| LockedSkill {
+                    name: skill.to_string(),
+                    path: format!("skills/{}", skill),
+                    hash: "sha256:0".to_string(),
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_hash_dir_tracks_content_and_ignores_git() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path();
+        fs::create_dir_all(root.join("scripts")).unwrap();
+        fs::write(root.join("SKILL.md"), "skill").unwrap();
+        fs::write(root.join("scripts/run.sh"), "echo hi").unwrap();
+        let original = hash_dir(root).unwrap();
+        assert!(original.starts_with("sha256:"));
+
+        fs::create_dir_all(root.join(".git")).unwrap();
+        fs::write(root.join(".git/HEAD"), "ref: refs/heads/main").unwrap();
+        assert_eq!(hash_dir(root).unwrap(), original);
+
+        fs::write(root.join("scripts/run.sh"), "echo bye").unwrap();
+        assert_ne!(hash_dir(root).unwrap(), original);
+    }
+
+    #[test]
+    fn test_upsert_and_remove_skills() {
+        let mut lockfile = Lockfile::default();
+        lockfile.upsert(locked_plugin("tools", "codex", &["lint", "review"]));
+        lockfile.upsert(locked_plugin("tools", "opencode", &["lint"]));
+        lockfile.upsert(locked_plugin("tools", "codex", &["lint", "review", "format"]));
+        assert_eq!(lockfile.plugins.len(), 2);
+
+        lockfile.remove_skills("opencode", &["lint".to_string()]);
+        assert_eq!(lockfile.plugins.len(), 1);
+        assert_eq!(lockfile.plugins[0].target, "codex");
+        assert_eq!(lockfile.plugins[0].skills.len(), 3);
+    }
+
+    #[test]
+    fn test_lockfile_round_trip() {
+        let temp = tempfile::tempdir().unwrap();
+        let path = temp.path().join(LOCKFILE_NAME);
+        let mut lockfile = Lockfile::default();
+        lockfile.upsert(locked_plugin("tools", "codex", &["lint"]));
+        lockfile.save(&path).unwrap();
+
+        let content = fs::read_to_string(&path).unwrap();
+        assert!(content.contains("\"ref\": null"));
+        let loaded = Lockfile::load(&path).unwrap().unwrap();
+        assert_eq!(loaded.version, LOCKFILE_VERSION);
+        assert_eq!(loaded.plugins[0].skills[0].path, "skills/lint");
+
+        fs::write(&path, content.replace("\"version\": 1", "\"version\": 2")).unwrap();
+        let err = Lockfile::load(&path).unwrap_err().to_string();
+        assert!(err.contains("has version 2"));
+    }
+
+    #[test]
+    fn test_load_rejects_paths_outside_the_tree() {
+        let temp = tempfile::tempdir().unwrap();
+        let path = temp.path().join(LOCKFILE_NAME);
+        let load = |plugin: LockedPlugin| {
+            let mut lockfile = Lockfile::default();
+            lockfile.upsert(plugin);
+            lockfile.save(&path).unwrap();
+            Lockfile::load(&path)
+        };
+        assert!(load(locked_plugin("tools", "codex", &["lint"])).is_ok());
+
+        for name in ["../..", "a/b", "..", "/etc", ""] {
+            let mut plugin = locked_plugin("tools", "codex", &["lint"]);
+            plugin.skills[0].name = name.to_string();
+            assert!(load(plugin).is_err(), "skill name {:?}", name);
+            let plugin = locked_plugin(name, "codex", &["lint"]);
+            assert!(load(plugin).is_err(), "plugin name {:?}", name);
+        }
+        for skill_path in ["/etc", "../outside", "skills/../../outside"] {
+            let mut plugin = locked_plugin("tools", "codex", &["lint"]);
+            plugin.skills[0].path = skill_path.to_string();
+            let err = format!("{:#}", load(plugin).unwrap_err());
+            assert!(err.contains("leaves the repository"), "{}", err);
+        }
+    }
+}
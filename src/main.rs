@@ -1,25 +1,73 @@
+mod cache;
 mod cli;
+mod config;
+mod lint;
+mod lockfile;
+mod manifest;
+mod marketplaces;
 mod model;
+mod npm;
+mod search;
+mod skill;
+mod tarball;
+mod targets;
 mod util;
 
 use anyhow::{anyhow, Context, Result};
+use cache::CacheKey;
 use clap::Parser;
-use cli::{Cli, Commands, Target};
+use cli::{CacheCommand, Cli, Commands, FetchMode, MarketplaceCommand, Scope};
 use crossterm::{cursor, event, execute, terminal};
+use lockfile::{LockedPlugin, LockedSkill, Lockfile};
 use log::{info, warn};
+use manifest::{Manifest, MarketplaceSpec};
 use model::{Marketplace, PluginSource, SourceDefinition};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::fs;
 use std::io::{self, Write};
 use std::path::{Path, PathBuf};
 use std::process::Command;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use targets::Target;
 
 #[derive(Debug, Serialize, Deserialize)]
 struct PluginInstallMetadata {
     version: Option<String>,
     skills: Vec<String>,
+    /// Marketplace the plugin was installed from, used by `skop update`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    marketplace: Option<String>,
+    /// Where the skills were copied from, shown by `skop info`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    source: Option<InstalledSource>,
+    /// Content hash of each skill as installed, to tell local edits apart.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    hashes: BTreeMap<String, String>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct InstalledSource {
+    url: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    subpath: Option<String>,
+    #[serde(rename = "ref", default, skip_serializing_if = "Option::is_none")]
+    git_ref: Option<String>,
+    commit: String,
+}
+
+impl InstalledSource {
+    fn from_locked(locked: &LockedPlugin) -> Self {
+        Self {
+            url: locked.url.clone(),
+            subpath: locked.subpath.clone(),
+            git_ref: locked.git_ref.clone(),
+            commit: locked.commit.clone(),
+        }
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -27,6 +75,24 @@ struct SkillEntry {
     name: String,
     path: PathBuf,
     target: Target,
+    scope: Scope,
+}
+
+impl SkillEntry {
+    fn location(&self) -> String {
+        format_location(&self.target, self.scope)
+    }
+
+    fn description(&self) -> Option<String> {
+        skill::description(&self.path)
+    }
+}
+
+fn format_location(target: &Target, scope: Scope) -> String {
+    match scope {
+        Scope::Project => target.to_string(),
+        Scope::Global => format!("{}, {}", target, scope),
+    }
 }
 
 #[derive(Clone, Copy)]
@@ -34,49 +100,379 @@ struct InstallOptions {
     dry_run: bool,
     max_depth: usize,
     quiet: bool,
+    scope: Scope,
+    fetch: FetchMode,
+    /// Plugin sources fetched concurrently while planning.
+    jobs: usize,
+    /// Serve marketplaces and remote sources from the cache only.
+    offline: bool,
 }
 
-#[derive(Hash, Eq, PartialEq)]
-struct CacheKey {
-    repo: String,
+#[derive(Default)]
+struct PluginInstall {
+    skills: Vec<String>,
+    origin: Option<InstallOrigin>,
+    /// Frontmatter descriptions of the skills, filled in by dry runs.
+    descriptions: HashMap<String, String>,
+}
+
+/// Where the installed skills of a plugin were copied from.
+struct InstallOrigin {
+    url: String,
+    subpath: Option<String>,
+    git_ref: Option<String>,
+    commit: String,
+    skill_paths: HashMap<String, String>,
+}
+
+#[derive(Clone, Copy)]
+struct RepoContext<'a> {
+    root: &'a Path,
+    url: &'a str,
+    git_ref: Option<&'a str>,
+    /// `None` when the checkout cannot be pinned, e.g. a local directory.
+    commit: Option<&'a str>,
+}
+
+/// Where a plugin's files come from, as resolved from its marketplace entry.
+#[derive(Debug)]
+struct PluginLocation {
+    url: String,
+    subpath: Option<String>,
     git_ref: Option<String>,
+    /// Commit the marketplace pins the plugin to; takes precedence over `git_ref`.
+    sha: Option<String>,
+}
+
+/// A checkout resolved during this run.
+struct CacheEntry {
+    root: PathBuf,
+    /// Commit checked out, if known; archives without a pax header have none.
+    commit: Option<String>,
 }
 
+/// Checkouts of plugin sources, backed by the persistent [`cache::Store`].
+/// Shared by reference across the workers of [`prefetch_plugins`].
 struct CloneCache {
-    entries: HashMap<CacheKey, tempfile::TempDir>,
+    store: cache::Store,
+    entries: Mutex<HashMap<CacheKey, CacheEntry>>,
+    /// Commits GitHub refs resolved to in this run, so that every subpath of a
+    /// repo is unpacked from the same one.
+    commits: OnceMap<String>,
+    /// GitHub archives downloaded in this run, by URL; unpacked once per subpath.
+    archives: OnceMap<Arc<tempfile::NamedTempFile>>,
+    /// Registry npm sources are resolved against.
+    npm_registry: String,
 }
 
-impl CloneCache {
+/// Values computed at most once per key, even when workers ask for the same key
+/// at the same time. Failures are not remembered.
+struct OnceMap<T> {
+    slots: Mutex<HashMap<String, Arc<Mutex<Option<T>>>>>,
+}
+
+impl<T: Clone> OnceMap<T> {
     fn new() -> Self {
         Self {
-            entries: HashMap::new(),
+            slots: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn get_or_try_init(&self, key: &str, init: impl FnOnce() -> Result<T>) -> Result<T> {
+        let slot = self.slots.lock().unwrap().entry(key.to_string()).or_default().clone();
+        let mut value = slot.lock().unwrap();
+        if let Some(value) = value.as_ref() {
+            return Ok(value.clone());
+        }
+        let computed = init()?;
+        *value = Some(computed.clone());
+        Ok(computed)
+    }
+}
+
+impl CloneCache {
+    fn new() -> Result<Self> {
+        Ok(Self::with_store(cache::Store::open()?))
+    }
+
+    fn with_store(store: cache::Store) -> Self {
+        Self {
+            store,
+            entries: Mutex::new(HashMap::new()),
+            commits: OnceMap::new(),
+            archives: OnceMap::new(),
+            npm_registry: npm::registry().to_string(),
+        }
+    }
+
+    /// Checks out a plugin location, at its pinned commit if it has one.
+    fn checkout(&self, location: &PluginLocation, options: InstallOptions) -> Result<PathBuf> {
+        let git_ref = location.sha.as_deref().or(location.git_ref.as_deref());
+        if let Some(package) = npm::package_of(&location.url) {
+            return self.get_or_install_package(package, git_ref, options);
+        }
+        if tarball::is_tarball_url(&location.url) {
+            // Nothing in a plain archive says which commit it holds.
+            if let Some(git_ref) = git_ref {
+                return Err(anyhow!(
+                    "{} is a plain archive and cannot be pinned to {}",
+                    location.url,
+                    git_ref
+                ));
+            }
+            if options.fetch != FetchMode::Git {
+                return self.get_or_download(&location.url, options);
+            }
+        }
+        if options.fetch != FetchMode::Git {
+            if tarball::github_archive_url(&location.url, None).is_some() {
+                let result =
+                    self.get_or_download_github(&location.url, git_ref, location.subpath.as_deref(), options);
+                match result {
+                    // Private GitHub repos only serve archives to authenticated requests.
+                    Err(err) if options.fetch == FetchMode::Auto => {
+                        info!("Falling back to git for {}: {:#}", location.url, err);
+                    }
+                    result => return result,
+                }
+            } else if options.fetch == FetchMode::Tarball
+                && util::local_marketplace_path(&location.url).is_none()
+            {
+                return Err(anyhow!("{} cannot be downloaded as a tarball", location.url));
+            }
+        }
+        match &location.sha {
+            Some(sha) => self.get_or_fetch_commit(&location.url, sha, options),
+            None => self.get_or_clone(&location.url, location.git_ref.as_deref(), options),
+        }
+    }
+
+    /// Commit of a directory returned by this cache; `None` for local directories.
+    fn commit_of(&self, root: &Path) -> Option<String> {
+        self.entries
+            .lock()
+            .unwrap()
+            .values()
+            .find(|entry| entry.root == root)
+            .and_then(|entry| entry.commit.clone())
+    }
+
+    fn remember(&self, key: CacheKey, entry: &cache::Entry) -> PathBuf {
+        let root = entry.root();
+        self.entries.lock().unwrap().insert(
+            key,
+            CacheEntry {
+                root: root.clone(),
+                commit: entry.meta.commit.clone(),
+            },
+        );
+        root
+    }
+
+    /// Returns a checkout resolved earlier in this run, or a stored one that is
+    /// still current. Stored git checkouts of a moved ref are fetched in place.
+    fn reuse(&self, key: &CacheKey, options: InstallOptions) -> Result<Option<PathBuf>> {
+        if let Some(entry) = self.entries.lock().unwrap().get(key) {
+            return Ok(Some(entry.root.clone()));
         }
+        let Some(mut entry) = self.store.get(key) else {
+            return Ok(None);
+        };
+        // Commits never move, and plain archive URLs are versioned by their name.
+        let mut refreshed = false;
+        if !key.is_immutable() && !tarball::is_tarball_url(&key.repo) && !options.offline {
+            match remote_commit(&key.repo, key.git_ref.as_deref()) {
+                Ok(remote) if remote.is_some() && remote == entry.meta.commit => {}
+                Ok(_) => {
+                    if entry.meta.kind == cache::EntryKind::Tarball {
+                        return Ok(None);
+                    }
+                    if !options.quiet {
+                        info!("Refreshing {} ...", key.repo);
+                    }
+                    let root = entry.root();
+                    let fetch_ref = key.git_ref.as_deref().unwrap_or("HEAD");
+                    let fetched = run_git(&root, &["fetch", "--quiet", "--depth", "1", "origin", fetch_ref])
+                        .and_then(|_| run_git(&root, &["checkout", "--quiet", "--detach", "FETCH_HEAD"]))
+                        .and_then(|_| run_git(&root, &["rev-parse", "HEAD"]));
+                    match fetched {
+                        Ok(commit) => entry.meta.commit = Some(commit),
+                        Err(err) => {
+                            info!("Re-cloning {}: {:#}", key.repo, err);
+                            return Ok(None);
+                        }
+                    }
+                    refreshed = true;
+                }
+                Err(err) => warn!(
+                    "Could not check {} for updates, using the cached copy: {:#}",
+                    key.repo, err
+                ),
+            }
+        }
+        self.store.touch(&mut entry, refreshed)?;
+        Ok(Some(self.remember(key.clone(), &entry)))
+    }
+
+    /// Downloads a plain `.tar.gz`; its URL names the version, so it is never refreshed.
+    fn get_or_download(&self, url: &str, options: InstallOptions) -> Result<PathBuf> {
+        let key = CacheKey {
+            repo: url.to_string(),
+            git_ref: None,
+            commit: None,
+            subpath: None,
+        };
+        if let Some(root) = self.reuse(&key, options)? {
+            return Ok(root);
+        }
+        if options.offline {
+            return Err(not_cached(&key));
+        }
+
+        let staging = self.store.stage()?;
+        if !options.quiet {
+            info!("Downloading {} ...", url);
+        }
+        let checkout_dir = staging.checkout_dir();
+        fs::create_dir_all(&checkout_dir)?;
+        let extracted = tarball::download(url, &checkout_dir, tarball::Layout::Plain)?;
+        let mut meta = cache::EntryMeta::new(&key, cache::EntryKind::Tarball);
+        meta.root = staging.relative_root(&extracted.root);
+        let entry = self.store.insert(&key, staging, meta)?;
+        Ok(self.remember(key, &entry))
+    }
+
+    /// Unpacks `subpath` (or everything) of a GitHub repo from the archive of the
+    /// commit `git_ref` points to.
+    fn get_or_download_github(
+        &self,
+        url: &str,
+        git_ref: Option<&str>,
+        subpath: Option<&str>,
+        options: InstallOptions,
+    ) -> Result<PathBuf> {
+        let (git_ref, commit) = match git_ref.filter(|value| is_commit_sha(value)) {
+            Some(commit) => (None, commit.to_string()),
+            None => (git_ref, self.github_commit(url, git_ref, options)?),
+        };
+        let key = |subpath: Option<&str>| CacheKey {
+            repo: url.to_string(),
+            git_ref: git_ref.map(|value| value.to_string()),
+            commit: Some(commit.clone()),
+            subpath: subpath.map(|value| value.to_string()),
+        };
+        // A full download serves every subpath.
+        for key in [key(None), key(subpath)] {
+            if let Some(root) = self.reuse(&key, options)? {
+                return Ok(root);
+            }
+        }
+        if options.offline {
+            return Err(not_cached(&key(subpath)));
+        }
+
+        let archive_url = tarball::github_archive_url(url, Some(&commit))
+            .ok_or_else(|| anyhow!("{} is not a GitHub repository", url))?;
+        let archive = self.archives.get_or_try_init(&archive_url, || {
+            if !options.quiet {
+                info!("Downloading {} at {} ...", url, commit);
+            }
+            let mut file = tempfile::NamedTempFile::new()?;
+            tarball::save(&archive_url, &mut file)?;
+            Ok(Arc::new(file))
+        })?;
+        let staging = self.store.stage()?;
+        let checkout_dir = staging.checkout_dir();
+        fs::create_dir_all(&checkout_dir)?;
+        let extracted = tarball::extract(
+            fs::File::open(archive.path())?,
+            &checkout_dir,
+            tarball::Layout::GitHub { subpath },
+        )
+        .with_context(|| format!("Failed to unpack {}", archive_url))?;
+        if let Some(archived) = extracted.commit.filter(|archived| *archived != commit) {
+            return Err(anyhow!(
+                "{} holds commit {}, not {}",
+                archive_url,
+                archived,
+                commit
+            ));
+        }
+        let key = key(subpath);
+        let mut meta = cache::EntryMeta::new(&key, cache::EntryKind::Tarball);
+        meta.commit = Some(commit.clone());
+        meta.root = staging.relative_root(&extracted.root);
+        let entry = self.store.insert(&key, staging, meta)?;
+        Ok(self.remember(key, &entry))
+    }
+
+    /// The commit `git_ref` (default: `HEAD`) of a GitHub repo points to, looked
+    /// up once per run. Offline, that of the newest cached download.
+    fn github_commit(&self, url: &str, git_ref: Option<&str>, options: InstallOptions) -> Result<String> {
+        let id = format!("{}#{}", url, git_ref.unwrap_or("HEAD"));
+        self.commits.get_or_try_init(&id, || {
+            if options.offline {
+                let entries = self.store.list()?;
+                return entries
+                    .into_iter()
+                    .filter(|entry| entry.meta.kind == cache::EntryKind::Tarball && entry.meta.url == url)
+                    .filter(|entry| entry.meta.git_ref.as_deref() == git_ref)
+                    .max_by_key(|entry| entry.meta.fetched_at)
+                    .and_then(|entry| entry.meta.pinned_commit)
+                    .ok_or_else(|| {
+                        not_cached(&CacheKey {
+                            repo: url.to_string(),
+                            git_ref: git_ref.map(|value| value.to_string()),
+                            commit: None,
+                            subpath: None,
+                        })
+                    });
+            }
+            remote_commit(url, git_ref)?
+                .ok_or_else(|| anyhow!("{} has no ref {}", url, git_ref.unwrap_or("HEAD")))
+        })
     }
 
     fn get_or_clone(
-        &mut self,
+        &self,
         git_url: &str,
         git_ref: Option<&str>,
         options: InstallOptions,
     ) -> Result<PathBuf> {
+        // `clone --branch` only takes branch and tag names.
+        if let Some(commit) = git_ref.filter(|value| is_commit_sha(value)) {
+            return self.get_or_fetch_commit(git_url, commit, options);
+        }
+        // Local marketplaces are used in place, including uncommitted changes.
+        if git_ref.is_none()
+            && let Some(dir) = util::local_marketplace_path(git_url)
+        {
+            return Ok(dir);
+        }
         let key = CacheKey {
             repo: git_url.to_string(),
             git_ref: git_ref.map(|value| value.to_string()),
+            commit: None,
+            subpath: None,
         };
-        if let Some(dir) = self.entries.get(&key) {
-            return Ok(dir.path().to_path_buf());
+        if let Some(root) = self.reuse(&key, options)? {
+            return Ok(root);
+        }
+        if options.offline && util::local_marketplace_path(git_url).is_none() {
+            return Err(not_cached(&key));
         }
 
-        let temp_dir = tempfile::Builder::new().prefix("skop_install").tempdir()?;
+        let staging = self.store.stage()?;
         if !options.quiet {
             info!("Cloning {} ...", git_url);
         }
+        let checkout_dir = staging.checkout_dir();
         let mut cmd = Command::new("git");
         cmd.arg("clone").arg("--depth").arg("1");
         if let Some(r) = git_ref {
             cmd.arg("--branch").arg(r);
         }
-        cmd.arg(git_url).arg(temp_dir.path());
+        cmd.arg(git_url).arg(&checkout_dir);
 
         let output = cmd.output().context("Failed to execute git clone")?;
         if !output.status.success() {
@@ -86,112 +482,826 @@ impl CloneCache {
             ));
         }
 
-        let path = temp_dir.path().to_path_buf();
-        self.entries.insert(key, temp_dir);
-        Ok(path)
+        let mut meta = cache::EntryMeta::new(&key, cache::EntryKind::Git);
+        meta.commit = Some(run_git(&checkout_dir, &["rev-parse", "HEAD"])?);
+        let entry = self.store.insert(&key, staging, meta)?;
+        Ok(self.remember(key, &entry))
+    }
+
+    fn get_or_fetch_commit(
+        &self,
+        git_url: &str,
+        commit: &str,
+        options: InstallOptions,
+    ) -> Result<PathBuf> {
+        let key = CacheKey {
+            repo: git_url.to_string(),
+            git_ref: None,
+            commit: Some(commit.to_string()),
+            subpath: None,
+        };
+        if let Some(root) = self.reuse(&key, options)? {
+            return Ok(root);
+        }
+        if options.offline && util::local_marketplace_path(git_url).is_none() {
+            return Err(not_cached(&key));
+        }
+
+        let staging = self.store.stage()?;
+        if !options.quiet {
+            info!("Fetching {} at {} ...", git_url, commit);
+        }
+        let checkout_dir = staging.checkout_dir();
+        fs::create_dir_all(&checkout_dir)?;
+        run_git(&checkout_dir, &["init", "--quiet"])?;
+        run_git(&checkout_dir, &["remote", "add", "origin", git_url])?;
+        // Servers that refuse unadvertised commits still serve them from a full fetch.
+        if run_git(&checkout_dir, &["fetch", "--quiet", "--depth", "1", "origin", commit]).is_err() {
+            run_git(&checkout_dir, &["fetch", "--quiet", "origin"])?;
+        }
+        run_git(&checkout_dir, &["checkout", "--quiet", "--detach", commit])
+            .with_context(|| format!("Commit {} not found in {}", commit, git_url))?;
+
+        let mut meta = cache::EntryMeta::new(&key, cache::EntryKind::Git);
+        meta.commit = Some(run_git(&checkout_dir, &["rev-parse", "HEAD"])?);
+        let entry = self.store.insert(&key, staging, meta)?;
+        Ok(self.remember(key, &entry))
+    }
+
+    /// Unpacks an npm package. Published versions never change, so the
+    /// resolved version serves as the commit of the checkout.
+    fn get_or_install_package(
+        &self,
+        package: &str,
+        version: Option<&str>,
+        options: InstallOptions,
+    ) -> Result<PathBuf> {
+        let url = npm::location_url(package);
+        let key = |version: String| CacheKey {
+            repo: url.clone(),
+            git_ref: None,
+            commit: Some(version),
+            subpath: None,
+        };
+        if options.offline {
+            let entries = self.store.list()?;
+            let cached = entries
+                .iter()
+                .filter(|entry| entry.meta.url == url)
+                .filter_map(|entry| entry.meta.pinned_commit.as_deref());
+            let missing = || {
+                not_cached(&CacheKey {
+                    repo: url.clone(),
+                    git_ref: version.map(|value| value.to_string()),
+                    commit: None,
+                    subpath: None,
+                })
+            };
+            let resolved =
+                npm::select_version(cached, &HashMap::new(), version)?.ok_or_else(missing)?;
+            return self.reuse(&key(resolved), options)?.ok_or_else(missing);
+        }
+
+        let release = npm::resolve(&self.npm_registry, package, version)?;
+        let key = key(release.version.clone());
+        if let Some(root) = self.reuse(&key, options)? {
+            return Ok(root);
+        }
+        let staging = self.store.stage()?;
+        if !options.quiet {
+            info!("Downloading {}@{} ...", package, release.version);
+        }
+        let checkout_dir = staging.checkout_dir();
+        fs::create_dir_all(&checkout_dir)?;
+        let extracted = npm::download(&release, &checkout_dir)?;
+        let mut meta = cache::EntryMeta::new(&key, cache::EntryKind::Npm);
+        meta.commit = Some(release.version);
+        meta.root = staging.relative_root(&extracted.root);
+        let entry = self.store.insert(&key, staging, meta)?;
+        Ok(self.remember(key, &entry))
+    }
+}
+
+fn not_cached(key: &CacheKey) -> anyhow::Error {
+    anyhow!("{} is not in the cache", key)
+}
+
+/// The commit `git_ref` (default: `HEAD`) points to on the remote, peeling annotated tags.
+fn remote_commit(git_url: &str, git_ref: Option<&str>) -> Result<Option<String>> {
+    let output = run_git(Path::new("."), &["ls-remote", git_url, git_ref.unwrap_or("HEAD")])?;
+    let mut first = None;
+    for line in output.lines() {
+        let Some((commit, name)) = line.split_once('\t') else {
+            continue;
+        };
+        if name.ends_with("^{}") {
+            return Ok(Some(commit.to_string()));
+        }
+        first.get_or_insert_with(|| commit.to_string());
+    }
+    Ok(first)
+}
+
+/// Full SHA-1 or SHA-256 object names; abbreviated ones cannot be fetched directly.
+fn is_commit_sha(value: &str) -> bool {
+    matches!(value.len(), 40 | 64) && value.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+fn run_git(dir: &Path, args: &[&str]) -> Result<String> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(args)
+        .output()
+        .context("Failed to execute git")?;
+    if !output.status.success() {
+        return Err(anyhow!(
+            "git {} failed: {}",
+            args.first().copied().unwrap_or_default(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
     }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
 }
 fn main() -> Result<()> {
     let cli = Cli::parse();
     init_logger(&cli);
+    let config = config::Config::load_merged()?;
+    targets::init_registry(&config)?;
+    npm::init_registry(&config);
+    marketplaces::init_registry(&config);
+    let fetch = cli.fetch;
+    let jobs = cli.jobs;
+    let offline = cli.offline;
+    let fetches = !offline
+        && match &cli.command {
+            Commands::Info { marketplace, .. } => marketplace.is_some(),
+            Commands::Marketplace { command } => {
+                matches!(command, MarketplaceCommand::Add { .. } | MarketplaceCommand::Refresh { .. })
+            }
+            Commands::Remove { .. } | Commands::List { .. } | Commands::Lint { .. } | Commands::Cache { .. } => {
+                false
+            }
+            _ => true,
+        };
 
     match cli.command {
         Commands::Add {
             dry_run,
             verbose: _,
             max_depth,
+            skills,
+            all_skills,
+            targets,
+            yes,
+            save,
+            global,
+            git_ref,
+            scan,
             repo,
         } => {
+            if yes && skills.is_empty() && !all_skills {
+                return Err(anyhow!(
+                    "--yes does not pick skills; name them with --skill or pass --all-skills"
+                ));
+            }
+            let spec = match util::local_marketplace_path(&repo) {
+                Some(_) if git_ref.is_some() => {
+                    return Err(anyhow!(
+                        "--ref cannot be used with the local marketplace {}; check out the ref there instead",
+                        repo
+                    ));
+                }
+                // skop.toml and skop.lock are shared, so project installs record
+                // the directory relative to the project root.
+                Some(_) if global => util::absolute_local_spec(&repo)?,
+                Some(_) => util::portable_local_spec(&repo)?,
+                None => repo,
+            };
+            let spec = match git_ref {
+                Some(git_ref) if util::split_marketplace_ref(&spec).1.is_some() => {
+                    return Err(anyhow!(
+                        "--ref {} conflicts with the ref already given in {}",
+                        git_ref,
+                        spec
+                    ));
+                }
+                Some(git_ref) => util::join_marketplace_ref(&spec, &git_ref),
+                None => spec,
+            };
+            let repo = marketplaces::resolve(&spec)?;
             let options = InstallOptions {
                 dry_run,
                 max_depth,
                 quiet: false,
+                scope: if global { Scope::Global } else { Scope::Project },
+                fetch,
+                jobs,
+                offline,
+            };
+            let mut clone_cache = CloneCache::new()?;
+            let marketplace = fetch_marketplace(&repo, &mut clone_cache, options)?;
+            let prompt_skills = skills.is_empty() && !all_skills;
+            if prompt_skills {
+                print_progress("Scanning marketplace to build skill list...")?;
+            } else {
+                info!("Scanning marketplace to build skill list...");
+            }
+            let mode = if scan { PlanMode::Scan } else { PlanMode::Declared };
+            let plan = plan_marketplace_skills(&marketplace, &repo, mode, options, &mut clone_cache)?;
+            let selected_skills = if prompt_skills {
+                select_skills(&plan)?
+            } else {
+                resolve_requested_skills(&plan, &skills)?
             };
-            let marketplace = fetch_marketplace(&repo)?;
-            let mut clone_cache = CloneCache::new();
-            print_progress("Scanning marketplace to build skill list...")?;
-            let plan = plan_marketplace_skills(&marketplace, &repo, options, &mut clone_cache)?;
-            let selected_skills = select_skills(&plan)?;
             if selected_skills.is_empty() {
                 println!("No skills selected.");
                 return Ok(());
             }
-            let targets = select_targets()?;
+            let targets = if !targets.is_empty() {
+                targets::registry().resolve(&targets)?
+            } else if yes {
+                return Err(anyhow!("--yes requires at least one --target"));
+            } else {
+                select_targets()?
+            };
             if targets.is_empty() {
                 println!("No targets selected.");
                 return Ok(());
             }
-            let mut failed = Vec::new();
-            for target in targets {
-                if let Err(err) = handle_add(
-                    target,
-                    &repo,
-                    &marketplace,
-                    &plan.by_plugin,
-                    options,
-                    &selected_skills,
-                    &mut clone_cache,
-                ) {
-                    eprintln!("Target {} failed: {}", target, err);
-                    failed.push(target);
-                }
-            }
-            if !failed.is_empty() {
-                return Err(anyhow!(
-                    "Failed targets: {}",
-                    failed
-                        .into_iter()
-                        .map(|t| t.to_string())
-                        .collect::<Vec<_>>()
-                        .join(", ")
-                ));
+            install_into_targets(
+                &targets,
+                MarketplaceSource {
+                    spec: &spec,
+                    repo: &repo,
+                },
+                &marketplace,
+                &plan,
+                options,
+                Selection {
+                    skills: &selected_skills,
+                    keep_installed: true,
+                    explicit: !skills.is_empty(),
+                },
+                &mut clone_cache,
+            )?;
+            if save && !dry_run {
+                save_to_manifest(&spec, &plan, &selected_skills, &targets)?;
             }
         }
-        Commands::Remove => {
-            handle_remove()?;
+        Commands::Remove {
+            skills,
+            plugins,
+            targets,
+            scope,
+            yes,
+        } => {
+            handle_remove(&skills, &plugins, &targets, scope, yes)?;
+        }
+        Commands::Install {
+            dry_run,
+            verbose: _,
+        } => {
+            let options = InstallOptions {
+                dry_run,
+                max_depth: 0,
+                quiet: false,
+                scope: Scope::Project,
+                fetch,
+                jobs,
+                offline,
+            };
+            handle_install_locked(options)?;
+        }
+        Commands::Sync {
+            dry_run,
+            verbose: _,
+            max_depth,
+        } => {
+            let options = InstallOptions {
+                dry_run,
+                max_depth,
+                quiet: false,
+                scope: Scope::Project,
+                fetch,
+                jobs,
+                offline,
+            };
+            handle_sync(options)?;
+        }
+        Commands::Update {
+            dry_run,
+            verbose: _,
+            max_depth,
+            targets,
+            scope,
+            plugins,
+        } => {
+            let options = InstallOptions {
+                dry_run,
+                max_depth,
+                quiet: false,
+                scope: Scope::Project,
+                fetch,
+                jobs,
+                offline,
+            };
+            handle_update(&plugins, &targets, scope, options)?;
+        }
+        Commands::Outdated {
+            verbose: _,
+            targets,
+            scope,
+        } => {
+            let options = InstallOptions {
+                dry_run: false,
+                max_depth: 0,
+                quiet: false,
+                scope: Scope::Project,
+                fetch,
+                jobs,
+                offline,
+            };
+            handle_outdated(&targets, scope, options)?;
+        }
+        Commands::List { scope, long } => {
+            handle_list(scope, long)?;
+        }
+        Commands::Search {
+            verbose: _,
+            scan,
+            query,
+        } => {
+            let options = InstallOptions {
+                dry_run: false,
+                max_depth: 1,
+                quiet: false,
+                scope: Scope::Project,
+                fetch,
+                jobs,
+                offline,
+            };
+            handle_search(&query.join(" "), scan, options)?;
+        }
+        Commands::Info {
+            verbose: _,
+            name,
+            marketplace,
+            scope,
+        } => {
+            let options = InstallOptions {
+                dry_run: false,
+                max_depth: 1,
+                quiet: false,
+                scope: Scope::Project,
+                fetch,
+                jobs,
+                offline,
+            };
+            handle_info(&name, marketplace.as_deref(), scope, options)?;
+        }
+        Commands::Lint { path, json } => {
+            handle_lint(&path, json)?;
         }
-        Commands::List => {
-            handle_list()?;
+        Commands::Cache { command } => {
+            handle_cache(command, &config)?;
+        }
+        Commands::Marketplace { command } => {
+            let options = InstallOptions {
+                dry_run: false,
+                max_depth: 0,
+                quiet: false,
+                scope: Scope::Project,
+                fetch,
+                jobs,
+                offline,
+            };
+            handle_marketplace(command, options)?;
         }
     }
 
+    // Keep the cache within its size limit after adding to it.
+    if fetches && let Err(err) = prune_cache(&config) {
+        warn!("Failed to prune the cache: {:#}", err);
+    }
     Ok(())
 }
 
-fn init_logger(cli: &Cli) {
-    let default_level = match cli.command {
-        Commands::Add { verbose, .. } => {
-            if verbose {
-                "info"
-            } else {
-                "warn"
-            }
-        }
-        Commands::Remove => "warn",
-        Commands::List => "warn",
-    };
-    let env = env_logger::Env::default().default_filter_or(default_level);
-    let _ = env_logger::Builder::from_env(env).try_init();
+fn prune_cache(config: &config::Config) -> Result<()> {
+    let limit = cache_size_limit(config, None)?;
+    for entry in cache::Store::open()?.prune(Some(limit), None)? {
+        info!("Evicted {} from the cache", entry.meta.url);
+    }
+    Ok(())
 }
 
-fn handle_add(
-    target: Target,
-    repo: &str,
-    marketplace: &Marketplace,
-    skills_by_plugin: &HashMap<String, Vec<String>>,
-    options: InstallOptions,
-    selected_skills: &HashSet<String>,
-    clone_cache: &mut CloneCache,
-) -> Result<()> {
-    let skills_dir = util::get_skills_dir(target);
-    if options.dry_run {
-        println!("Dry run: no files will be modified.");
-        println!("Target: {}", target);
+/// The absolute form of a local marketplace directory, so it still resolves from
+/// another working directory.
+fn handle_lint(path: &str, json: bool) -> Result<()> {
+    let root = fs::canonicalize(path).with_context(|| format!("Failed to open {}", path))?;
+    let report = lint::lint_marketplace(&root);
+    let errors = report.count(lint::Severity::Error);
+    let warnings = report.count(lint::Severity::Warning);
+    if json {
+        println!("{}", serde_json::to_string_pretty(&report)?);
     } else {
-        fs::create_dir_all(&skills_dir).context("Failed to create skills directory")?;
+        println!(
+            "Checked {}: {} plugin(s), {} skill(s)",
+            report.marketplace.display(),
+            report.plugins,
+            report.skills
+        );
+        for finding in &report.findings {
+            println!("{}", finding);
+        }
+        if !report.remote.is_empty() {
+            println!("Not checked (remote sources): {}", report.remote.join(", "));
+        }
+        println!("{} error(s), {} warning(s)", errors, warnings);
     }
-
-    let plugin_root = marketplace
+    if errors > 0 {
+        return Err(anyhow!("Lint found {} error(s)", errors));
+    }
+    Ok(())
+}
+
+fn handle_marketplace(command: MarketplaceCommand, options: InstallOptions) -> Result<()> {
+    let origin = |project: bool| {
+        if project {
+            marketplaces::Origin::Project
+        } else {
+            marketplaces::Origin::User
+        }
+    };
+    match command {
+        MarketplaceCommand::Add {
+            repo,
+            name,
+            project,
+        } => {
+            if let Some(name) = &name {
+                marketplaces::check_name(name)?;
+            }
+            let origin = origin(project);
+            // The user config applies in every directory, so pin local paths down;
+            // the project config is shared, so keep them relative to the project.
+            let repo = match origin {
+                marketplaces::Origin::User => util::absolute_local_spec(&repo)?,
+                marketplaces::Origin::Project => util::portable_local_spec(&repo)?,
+            };
+            let mut clone_cache = CloneCache::new()?;
+            let marketplace = fetch_marketplace(&repo, &mut clone_cache, options)?;
+            let name = name.unwrap_or(marketplace.name);
+            if marketplaces::register(origin, &name, &repo)? {
+                println!(
+                    "Registered {} as {} ({} plugin(s)) in {}",
+                    repo,
+                    name,
+                    marketplace.plugins.len(),
+                    origin.path()?.display()
+                );
+            } else {
+                println!("{} is already registered as {}", repo, name);
+            }
+        }
+        MarketplaceCommand::List => {
+            let entries = marketplaces::list()?;
+            if entries.is_empty() {
+                println!("No marketplaces registered.");
+                return Ok(());
+            }
+            let store = cache::Store::open()?;
+            let rows: Vec<[String; 4]> = entries
+                .into_iter()
+                .map(|(origin, name, repo)| {
+                    let plugins = cached_marketplace(&store, &repo)
+                        .map(|marketplace| marketplace.plugins.len().to_string())
+                        .unwrap_or_else(|| "-".to_string());
+                    // The warning when loading the config says why.
+                    let ignored = marketplaces::registry().get(&name) != Some(&repo);
+                    let origin = if ignored {
+                        format!("{} (ignored)", origin)
+                    } else {
+                        origin.to_string()
+                    };
+                    [name, repo, origin, plugins]
+                })
+                .collect();
+            print_table(&["NAME", "MARKETPLACE", "CONFIG", "PLUGINS"], &rows);
+        }
+        MarketplaceCommand::Remove { name, project } => {
+            let repo = marketplaces::unregister(origin(project), &name)?;
+            println!("Removed {} ({})", name, repo);
+        }
+        MarketplaceCommand::Refresh { names } => {
+            if options.offline {
+                return Err(anyhow!("Refreshing marketplaces needs the network; drop --offline"));
+            }
+            let registry = marketplaces::registry();
+            let selected: Vec<(&String, &String)> = if names.is_empty() {
+                registry.iter().collect()
+            } else {
+                names
+                    .iter()
+                    .map(|name| {
+                        registry
+                            .get_key_value(name)
+                            .ok_or_else(|| anyhow!("No marketplace named {} is registered", name))
+                    })
+                    .collect::<Result<_>>()?
+            };
+            if selected.is_empty() {
+                println!("No marketplaces registered.");
+                return Ok(());
+            }
+            let mut clone_cache = CloneCache::new()?;
+            let mut failed = 0;
+            for (name, repo) in selected {
+                match fetch_marketplace(repo, &mut clone_cache, options) {
+                    Ok(marketplace) => {
+                        println!("Refreshed {} ({} plugin(s))", name, marketplace.plugins.len())
+                    }
+                    Err(err) => {
+                        warn!("Failed to refresh {}: {:#}", name, err);
+                        failed += 1;
+                    }
+                }
+            }
+            if failed > 0 {
+                return Err(anyhow!("Failed to refresh {} marketplace(s)", failed));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// The marketplace as last fetched, without going to the network.
+fn cached_marketplace(store: &cache::Store, spec: &str) -> Option<Marketplace> {
+    if let Some(dir) = util::local_marketplace_path(spec) {
+        return load_marketplace_file(&dir).ok();
+    }
+    serde_json::from_str(&store.load_marketplace(spec)?).ok()
+}
+
+fn cache_size_limit(config: &config::Config, max_size: Option<&str>) -> Result<u64> {
+    match max_size.or(config.cache.max_size.as_deref()) {
+        Some(value) => cache::parse_size(value),
+        None => Ok(cache::DEFAULT_MAX_SIZE),
+    }
+}
+
+fn handle_cache(command: CacheCommand, config: &config::Config) -> Result<()> {
+    let store = cache::Store::open()?;
+    match command {
+        CacheCommand::List => {
+            let entries = store.list()?;
+            if entries.is_empty() {
+                println!("Cache is empty ({}).", store.root().display());
+                return Ok(());
+            }
+            let now = cache::now();
+            let rows: Vec<[String; 6]> = entries
+                .iter()
+                .map(|entry| {
+                    let meta = &entry.meta;
+                    [
+                        meta.url.clone(),
+                        meta.git_ref.clone().unwrap_or_else(|| "-".to_string()),
+                        meta.commit
+                            .as_deref()
+                            .map(|commit| commit.chars().take(12).collect())
+                            .unwrap_or_else(|| "-".to_string()),
+                        match meta.kind {
+                            cache::EntryKind::Git => "git".to_string(),
+                            cache::EntryKind::Tarball => "tarball".to_string(),
+                            cache::EntryKind::Npm => "npm".to_string(),
+                        },
+                        cache::format_size(meta.size),
+                        format_age(now.saturating_sub(meta.last_used)),
+                    ]
+                })
+                .collect();
+            print_table(&["URL", "REF", "COMMIT", "KIND", "SIZE", "LAST USED"], &rows);
+            let total = entries.iter().map(|entry| entry.meta.size).sum();
+            println!(
+                "\n{} entr{}, {} in {}",
+                entries.len(),
+                if entries.len() == 1 { "y" } else { "ies" },
+                cache::format_size(total),
+                store.root().display()
+            );
+        }
+        CacheCommand::Clean => {
+            let removed = store.clean()?;
+            println!("Removed {} cache entr{}.", removed, if removed == 1 { "y" } else { "ies" });
+        }
+        CacheCommand::Prune {
+            older_than,
+            max_size,
+        } => {
+            let limit = cache_size_limit(config, max_size.as_deref())?;
+            let removed = store.prune(Some(limit), Some(older_than))?;
+            let freed = removed.iter().map(|entry| entry.meta.size).sum();
+            for entry in &removed {
+                println!("Removed {}", entry.meta.url);
+            }
+            println!(
+                "Pruned {} cache entr{}, freed {}.",
+                removed.len(),
+                if removed.len() == 1 { "y" } else { "ies" },
+                cache::format_size(freed)
+            );
+        }
+    }
+    Ok(())
+}
+
+fn format_age(seconds: u64) -> String {
+    match seconds {
+        0..60 => "just now".to_string(),
+        60..3600 => format!("{}m ago", seconds / 60),
+        3600..86400 => format!("{}h ago", seconds / 3600),
+        _ => format!("{}d ago", seconds / 86400),
+    }
+}
+
+fn init_logger(cli: &Cli) {
+    let default_level = match cli.command {
+        Commands::Add { verbose, .. } => {
+            if verbose {
+                "info"
+            } else {
+                "warn"
+            }
+        }
+        Commands::Install { verbose, .. } => {
+            if verbose {
+                "info"
+            } else {
+                "warn"
+            }
+        }
+        Commands::Sync { verbose, .. } => {
+            if verbose {
+                "info"
+            } else {
+                "warn"
+            }
+        }
+        Commands::Update { verbose, .. } => {
+            if verbose {
+                "info"
+            } else {
+                "warn"
+            }
+        }
+        Commands::Outdated { verbose, .. } => {
+            if verbose {
+                "info"
+            } else {
+                "warn"
+            }
+        }
+        Commands::Remove { .. } => "warn",
+        Commands::List { .. } => "warn",
+        Commands::Search { verbose, .. } => {
+            if verbose {
+                "info"
+            } else {
+                "warn"
+            }
+        }
+        Commands::Info { verbose, .. } => {
+            if verbose {
+                "info"
+            } else {
+                "warn"
+            }
+        }
+        Commands::Lint { .. } => "warn",
+        Commands::Cache { .. } => "warn",
+        Commands::Marketplace { .. } => "warn",
+    };
+    let env = env_logger::Env::default().default_filter_or(default_level);
+    let _ = env_logger::Builder::from_env(env).try_init();
+}
+
+/// The skills [`install_into_targets`] installs.
+#[derive(Clone, Copy)]
+struct Selection<'a> {
+    skills: &'a HashSet<String>,
+    /// Keep the skills of a plugin installed earlier when it is reinstalled, as
+    /// `skop add` does; `sync` and `update` install exactly `skills`.
+    keep_installed: bool,
+    /// `skills` were named by the user, so a plugin that turns out not to contain
+    /// one of them fails the command instead of only logging a warning.
+    explicit: bool,
+}
+
+/// A marketplace as the user named it, which is what gets recorded, and the
+/// repository that stands for. They differ for registered names, which should
+/// keep following their registration.
+#[derive(Clone, Copy)]
+struct MarketplaceSource<'a> {
+    spec: &'a str,
+    repo: &'a str,
+}
+
+fn install_into_targets(
+    targets: &[Target],
+    source: MarketplaceSource,
+    marketplace: &Marketplace,
+    plan: &SkillPlan,
+    options: InstallOptions,
+    selection: Selection,
+    clone_cache: &mut CloneCache,
+) -> Result<usize> {
+    let mut failed = Vec::new();
+    let mut installed = 0;
+    let mut locked = Vec::new();
+    let mut missing = Vec::new();
+    for target in targets {
+        match handle_add(
+            target,
+            source,
+            marketplace,
+            &plan.by_plugin,
+            options,
+            selection,
+            clone_cache,
+        ) {
+            Ok(outcome) => {
+                installed += outcome.installed;
+                locked.extend(outcome.locked);
+                missing.extend(outcome.missing);
+            }
+            Err(err) => {
+                eprintln!(
+                    "Target {} failed: {}",
+                    format_location(target, options.scope),
+                    err
+                );
+                failed.push(target);
+            }
+        }
+    }
+    if !locked.is_empty() {
+        update_lockfile(|lockfile| {
+            for plugin in locked {
+                lockfile.upsert(plugin);
+            }
+        })?;
+    }
+    if !failed.is_empty() {
+        return Err(anyhow!(
+            "Failed targets: {}",
+            failed
+                .into_iter()
+                .map(|t| t.to_string())
+                .collect::<Vec<_>>()
+                .join(", ")
+        ));
+    }
+    if !missing.is_empty() {
+        missing.sort();
+        missing.dedup();
+        return Err(anyhow!(
+            "Requested skill(s) not found in their plugin: {}",
+            missing.join(", ")
+        ));
+    }
+    Ok(installed)
+}
+
+/// Plugins `handle_add` (re)installed into one target, and the lock entries for them.
+#[derive(Default)]
+struct AddOutcome {
+    installed: usize,
+    locked: Vec<LockedPlugin>,
+    /// Explicitly requested skills the fetched plugins do not contain, as `skill (plugin)`.
+    missing: Vec<String>,
+}
+
+fn handle_add(
+    target: &Target,
+    source: MarketplaceSource,
+    marketplace: &Marketplace,
+    skills_by_plugin: &HashMap<String, Vec<String>>,
+    options: InstallOptions,
+    selection: Selection,
+    clone_cache: &mut CloneCache,
+) -> Result<AddOutcome> {
+    let repo = source.repo;
+    let skills_dir = util::get_skills_dir(target, options.scope)?;
+    let mut outcome = AddOutcome::default();
+    if options.dry_run {
+        println!("Dry run: no files will be modified.");
+        println!("Target: {}", format_location(target, options.scope));
+    } else {
+        fs::create_dir_all(&skills_dir).context("Failed to create skills directory")?;
+    }
+
+    let plugin_root = marketplace
         .metadata
         .as_ref()
         .and_then(|metadata| metadata.plugin_root.as_deref());
@@ -202,7 +1312,7 @@ fn handle_add(
             .map(|skills| {
                 skills
                     .iter()
-                    .filter(|skill| selected_skills.contains(*skill))
+                    .filter(|skill| selection.skills.contains(*skill))
                     .cloned()
                     .collect::<HashSet<_>>()
             })
@@ -211,13 +1321,36 @@ fn handle_add(
             continue;
         }
         let metadata = read_plugin_metadata(&skills_dir, &plugin.name);
-        let should_install = should_install_plugin(plugin, metadata.as_ref());
+        let mut should_install = should_install_plugin(plugin, metadata.as_ref());
+        if !should_install
+            && let Some(existing) = &metadata
+            && selected_for_plugin
+                .iter()
+                .any(|skill| !existing.skills.contains(skill))
+        {
+            info!("Plugin {} is missing selected skills, reinstalling.", plugin.name);
+            should_install = true;
+        }
 
         if !should_install {
             info!("Plugin {} is up to date.", plugin.name);
             continue;
         }
 
+        // Reinstalling replaces the plugin's skills, so keep the ones installed earlier.
+        let mut selected_for_plugin = selected_for_plugin;
+        if selection.keep_installed
+            && let (Some(existing), Some(available)) = (&metadata, skills_by_plugin.get(&plugin.name))
+        {
+            selected_for_plugin.extend(
+                existing
+                    .skills
+                    .iter()
+                    .filter(|skill| available.contains(*skill))
+                    .cloned(),
+            );
+        }
+
         if !options.dry_run {
             remove_legacy_plugin_dir(&skills_dir, &plugin.name)?;
             if let Some(existing) = &metadata {
@@ -225,113 +1358,1166 @@ fn handle_add(
             }
         }
 
-        if options.dry_run {
-            println!("Plugin: {}", plugin.name);
-            println!("  marketplace.json: present");
-            if !should_install {
-                println!("  status: up to date");
-            } else {
-                println!("  status: would install/update");
-            }
-        }
+        if options.dry_run {
+            println!("Plugin: {}", plugin.name);
+            println!("  marketplace.json: present");
+            if !should_install {
+                println!("  status: up to date");
+            } else {
+                println!("  status: would install/update");
+            }
+        }
+
+        let installed = install_plugin(
+            plugin,
+            &skills_dir,
+            repo,
+            plugin_root,
+            options,
+            Some(&selected_for_plugin),
+            clone_cache,
+        )?;
+        let installed_skills = installed.skills;
+        // Skills planned from marketplace.json may not exist in the plugin.
+        let (mut requested, mut missing): (Vec<&str>, Vec<&str>) = selected_for_plugin
+            .iter()
+            .filter(|skill| !installed_skills.contains(*skill))
+            .map(|skill| skill.as_str())
+            .partition(|skill| selection.explicit && selection.skills.contains(*skill));
+        if !missing.is_empty() {
+            missing.sort();
+            warn!(
+                "Plugin {} does not contain the declared skill(s): {}",
+                plugin.name,
+                missing.join(", ")
+            );
+        }
+        requested.sort();
+        outcome
+            .missing
+            .extend(requested.into_iter().map(|skill| format!("{} ({})", skill, plugin.name)));
+
+        if options.dry_run {
+            println!(
+                "  skills: {}",
+                if installed_skills.is_empty() {
+                    "none".to_string()
+                } else {
+                    installed_skills.join(", ")
+                }
+            );
+            continue;
+        }
+
+        let mut hashes = BTreeMap::new();
+        for skill in &installed_skills {
+            hashes.insert(skill.clone(), lockfile::hash_dir(&skills_dir.join(skill))?);
+        }
+        let new_metadata = PluginInstallMetadata {
+            version: plugin.version.clone(),
+            skills: installed_skills.clone(),
+            marketplace: Some(source.spec.to_string()),
+            source: installed.origin.as_ref().map(|origin| InstalledSource {
+                url: origin.url.clone(),
+                subpath: origin.subpath.clone(),
+                git_ref: origin.git_ref.clone(),
+                commit: origin.commit.clone(),
+            }),
+            hashes: hashes.clone(),
+        };
+        write_plugin_metadata(&skills_dir, &plugin.name, &new_metadata)?;
+        outcome.installed += 1;
+        info!(
+            "Installed {} ({} skill(s))",
+            plugin.name,
+            installed_skills.len()
+        );
+
+        // skop.lock only pins project installs.
+        if let Some(origin) = installed.origin.filter(|_| options.scope == Scope::Project) {
+            let skills = installed_skills
+                .iter()
+                .map(|skill| LockedSkill {
+                    name: skill.clone(),
+                    path: origin.skill_paths.get(skill).cloned().unwrap_or_default(),
+                    hash: hashes[skill].clone(),
+                })
+                .collect();
+            outcome.locked.push(LockedPlugin {
+                name: plugin.name.clone(),
+                target: target.to_string(),
+                marketplace: source.spec.to_string(),
+                version: plugin.version.clone(),
+                url: util::portable_local_spec(&origin.url)?,
+                subpath: origin.subpath,
+                git_ref: origin.git_ref,
+                commit: origin.commit,
+                skills,
+            });
+        }
+    }
+
+    Ok(outcome)
+}
+
+fn save_to_manifest(
+    repo: &str,
+    plan: &SkillPlan,
+    selected_skills: &HashSet<String>,
+    targets: &[Target],
+) -> Result<()> {
+    let path = util::get_manifest_path();
+    let mut manifest = Manifest::load(&path)?.unwrap_or_default();
+    let mut skills: Vec<String> = if selected_skills.len() == plan.all_skills.len() {
+        Vec::new()
+    } else {
+        selected_skills.iter().cloned().collect()
+    };
+    skills.sort();
+    manifest.upsert(MarketplaceSpec {
+        repo: repo.to_string(),
+        plugins: Vec::new(),
+        skills,
+        targets: targets.iter().map(|target| target.to_string()).collect(),
+    });
+    manifest.save(&path)?;
+    println!("Saved selection to {}", manifest::MANIFEST_NAME);
+    Ok(())
+}
+
+fn handle_sync(options: InstallOptions) -> Result<()> {
+    let path = util::get_manifest_path();
+    let Some(manifest) = Manifest::load(&path)? else {
+        return Err(anyhow!("No {} found in the current directory", manifest::MANIFEST_NAME));
+    };
+
+    let mut clone_cache = CloneCache::new()?;
+    let mut desired: HashMap<Target, HashSet<String>> = HashMap::new();
+    for spec in &manifest.marketplaces {
+        let targets = targets::registry().resolve(manifest.targets_for(spec))?;
+        if targets.is_empty() {
+            return Err(anyhow!(
+                "No targets declared for {} in {}",
+                spec.repo,
+                manifest::MANIFEST_NAME
+            ));
+        }
+        let repo = marketplaces::resolve(&spec.repo)?;
+        let marketplace = fetch_marketplace(&repo, &mut clone_cache, options)?;
+        info!("Scanning {} to build skill list...", repo);
+        let plan = plan_marketplace_skills(
+            &marketplace,
+            &repo,
+            PlanMode::Declared,
+            options,
+            &mut clone_cache,
+        )?;
+        let selected_skills = resolve_spec_skills(&plan, spec)?;
+        for target in &targets {
+            desired
+                .entry(target.clone())
+                .or_default()
+                .extend(selected_skills.iter().cloned());
+        }
+        install_into_targets(
+            &targets,
+            MarketplaceSource {
+                spec: &spec.repo,
+                repo: &repo,
+            },
+            &marketplace,
+            &plan,
+            options,
+            Selection {
+                skills: &selected_skills,
+                keep_installed: false,
+                explicit: !spec.skills.is_empty(),
+            },
+            &mut clone_cache,
+        )?;
+    }
+
+    let unlisted = collect_unlisted_skills(&desired)?;
+    if unlisted.is_empty() {
+        return Ok(());
+    }
+    if options.dry_run {
+        for entry in &unlisted {
+            println!("Would remove: {} ({})", entry.name, entry.target);
+        }
+        return Ok(());
+    }
+    for entry in &unlisted {
+        println!("Removing skill: {} ({})", entry.name, entry.target);
+    }
+    remove_skill_entries(&unlisted)
+}
+
+struct InstalledPlugin {
+    name: String,
+    target: Target,
+    scope: Scope,
+    metadata: PluginInstallMetadata,
+}
+
+fn collect_installed_plugins(targets: &[Target], scopes: &[Scope]) -> Result<Vec<InstalledPlugin>> {
+    let mut plugins = Vec::new();
+    for scope in scopes {
+        for target in targets {
+            if !targets::registry().supports(target, *scope)
+                || is_listed_as_global(target, *scope, scopes)?
+            {
+                continue;
+            }
+            let skills_dir = util::get_skills_dir(target, *scope)?;
+            for (name, metadata) in read_all_plugin_metadata(&skills_dir) {
+                plugins.push(InstalledPlugin {
+                    name,
+                    target: target.clone(),
+                    scope: *scope,
+                    metadata,
+                });
+            }
+        }
+    }
+    Ok(plugins)
+}
+
+fn handle_update(
+    plugins: &[String],
+    targets: &[String],
+    scope: Option<Scope>,
+    options: InstallOptions,
+) -> Result<()> {
+    let targets = resolve_target_filter(targets)?;
+    let scopes = Scope::selected(scope);
+    let mut installed = collect_installed_plugins(&targets, &scopes)?;
+    if !plugins.is_empty() {
+        let missing: Vec<&str> = plugins
+            .iter()
+            .filter(|plugin| !installed.iter().any(|entry| &entry.name == *plugin))
+            .map(|plugin| plugin.as_str())
+            .collect();
+        if !missing.is_empty() {
+            return Err(anyhow!("Plugin(s) not installed: {}", missing.join(", ")));
+        }
+        installed.retain(|entry| plugins.contains(&entry.name));
+    }
+    if installed.is_empty() {
+        println!("No plugins installed.");
+        return Ok(());
+    }
+
+    // Group by marketplace so each marketplace.json is fetched once.
+    let mut by_marketplace: Vec<(String, Vec<InstalledPlugin>)> = Vec::new();
+    for entry in installed {
+        let Some(marketplace) = entry.metadata.marketplace.clone() else {
+            warn!(
+                "Plugin {} ({}) has no recorded marketplace; re-install it with `skop add` to enable updates.",
+                entry.name,
+                format_location(&entry.target, entry.scope)
+            );
+            continue;
+        };
+        match by_marketplace.iter_mut().find(|(repo, _)| *repo == marketplace) {
+            Some((_, entries)) => entries.push(entry),
+            None => by_marketplace.push((marketplace, vec![entry])),
+        }
+    }
+
+    let mut clone_cache = CloneCache::new()?;
+    let mut updated = 0;
+    for (spec, entries) in by_marketplace {
+        let repo = marketplaces::resolve(&spec)?;
+        let marketplace = fetch_marketplace(&repo, &mut clone_cache, options)?;
+        for entry in &entries {
+            if !marketplace.plugins.iter().any(|plugin| plugin.name == entry.name) {
+                warn!("Plugin {} is no longer listed in {}", entry.name, repo);
+            }
+        }
+        for scope in &scopes {
+            for target in &targets {
+                let mut by_plugin = HashMap::new();
+                let mut selected = HashSet::new();
+                for entry in entries
+                    .iter()
+                    .filter(|entry| entry.target == *target && entry.scope == *scope)
+                {
+                    by_plugin.insert(entry.name.clone(), entry.metadata.skills.clone());
+                    selected.extend(entry.metadata.skills.iter().cloned());
+                }
+                if by_plugin.is_empty() {
+                    continue;
+                }
+                let plan = SkillPlan {
+                    by_plugin,
+                    all_skills: selected.iter().cloned().collect(),
+                    descriptions: HashMap::new(),
+                };
+                updated += install_into_targets(
+                    std::slice::from_ref(target),
+                    MarketplaceSource {
+                        spec: &spec,
+                        repo: &repo,
+                    },
+                    &marketplace,
+                    &plan,
+                    InstallOptions {
+                        scope: *scope,
+                        ..options
+                    },
+                    Selection {
+                        skills: &selected,
+                        keep_installed: false,
+                        explicit: false,
+                    },
+                    &mut clone_cache,
+                )?;
+            }
+        }
+    }
+
+    if !options.dry_run {
+        if updated == 0 {
+            println!("All plugins are up to date.");
+        } else {
+            println!("Updated {} plugin(s).", updated);
+        }
+    }
+    Ok(())
+}
+
+struct OutdatedRow {
+    plugin: String,
+    marketplace: String,
+    installed: String,
+    latest: String,
+    status: &'static str,
+    locations: Vec<String>,
+}
+
+fn handle_outdated(targets: &[String], scope: Option<Scope>, options: InstallOptions) -> Result<()> {
+    let targets = resolve_target_filter(targets)?;
+    let installed = collect_installed_plugins(&targets, &Scope::selected(scope))?;
+    if installed.is_empty() {
+        println!("No plugins installed.");
+        return Ok(());
+    }
+
+    let mut clone_cache = CloneCache::new()?;
+    let mut marketplaces: HashMap<String, Marketplace> = HashMap::new();
+    let mut rows: Vec<OutdatedRow> = Vec::new();
+    for entry in installed {
+        let installed_version = entry.metadata.version.clone().unwrap_or_else(|| "-".to_string());
+        let (marketplace_name, latest, status) = match &entry.metadata.marketplace {
+            None => ("-".to_string(), "-".to_string(), "unknown"),
+            Some(repo) => {
+                if !marketplaces.contains_key(repo) {
+                    let marketplace =
+                        marketplaces::resolve(repo)
+                            .and_then(|resolved| fetch_marketplace(&resolved, &mut clone_cache, options))
+                            .with_context(|| format!("Failed to check {}", repo))?;
+                    marketplaces.insert(repo.clone(), marketplace);
+                }
+                match marketplaces[repo]
+                    .plugins
+                    .iter()
+                    .find(|plugin| plugin.name == entry.name)
+                {
+                    None => (repo.clone(), "-".to_string(), "removed"),
+                    Some(plugin) => {
+                        let status = match compare_plugin_version(plugin, Some(&entry.metadata)) {
+                            VersionStatus::Outdated => "outdated",
+                            VersionStatus::UpToDate => "up to date",
+                            _ => "unknown",
+                        };
+                        let latest = plugin.version.clone().unwrap_or_else(|| "-".to_string());
+                        (repo.clone(), latest, status)
+                    }
+                }
+            }
+        };
+        match rows.iter_mut().find(|row| {
+            row.plugin == entry.name
+                && row.marketplace == marketplace_name
+                && row.installed == installed_version
+        }) {
+            Some(row) => row.locations.push(format_location(&entry.target, entry.scope)),
+            None => rows.push(OutdatedRow {
+                plugin: entry.name,
+                marketplace: marketplace_name,
+                installed: installed_version,
+                latest,
+                status,
+                locations: vec![format_location(&entry.target, entry.scope)],
+            }),
+        }
+    }
+    rows.sort_by(|a, b| a.plugin.cmp(&b.plugin).then(a.installed.cmp(&b.installed)));
+
+    let table: Vec<[String; 6]> = rows
+        .iter()
+        .map(|row| {
+            [
+                row.plugin.clone(),
+                row.installed.clone(),
+                row.latest.clone(),
+                row.status.to_string(),
+                row.locations.join("; "),
+                row.marketplace.clone(),
+            ]
+        })
+        .collect();
+    print_table(
+        &["PLUGIN", "INSTALLED", "LATEST", "STATUS", "TARGETS", "MARKETPLACE"],
+        &table,
+    );
+
+    let outdated = rows.iter().filter(|row| row.status == "outdated").count();
+    if outdated > 0 {
+        return Err(anyhow!("{} plugin(s) are outdated", outdated));
+    }
+    Ok(())
+}
+
+fn print_table<const N: usize>(header: &[&str; N], rows: &[[String; N]]) {
+    let mut widths = header.map(|column| column.len());
+    for row in rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = usize::max(*width, cell.chars().count());
+        }
+    }
+    let format_row = |cells: Vec<&str>| {
+        cells
+            .iter()
+            .zip(widths)
+            .map(|(cell, width)| format!("{:<width$}", cell, width = width))
+            .collect::<Vec<_>>()
+            .join("  ")
+            .trim_end()
+            .to_string()
+    };
+    println!("{}", format_row(header.to_vec()));
+    for row in rows {
+        println!("{}", format_row(row.iter().map(|cell| cell.as_str()).collect()));
+    }
+}
+
+fn resolve_spec_skills(plan: &SkillPlan, spec: &MarketplaceSpec) -> Result<HashSet<String>> {
+    if spec.plugins.is_empty() {
+        return resolve_requested_skills(plan, &spec.skills);
+    }
+    let mut by_plugin = HashMap::new();
+    let mut all = HashSet::new();
+    for plugin in &spec.plugins {
+        let Some(skills) = plan.by_plugin.get(plugin) else {
+            let mut available: Vec<&str> = plan.by_plugin.keys().map(|name| name.as_str()).collect();
+            available.sort();
+            return Err(anyhow!(
+                "Unknown plugin {} in {}. Available plugins: {}",
+                plugin,
+                spec.repo,
+                available.join(", ")
+            ));
+        };
+        all.extend(skills.iter().cloned());
+        by_plugin.insert(plugin.clone(), skills.clone());
+    }
+    let mut all_skills: Vec<String> = all.into_iter().collect();
+    all_skills.sort();
+    let plan = SkillPlan {
+        by_plugin,
+        all_skills,
+        descriptions: HashMap::new(),
+    };
+    resolve_requested_skills(&plan, &spec.skills)
+}
+
+/// Managed skills (recorded in `.skop` metadata) that the manifest no longer lists.
+fn collect_unlisted_skills(desired: &HashMap<Target, HashSet<String>>) -> Result<Vec<SkillEntry>> {
+    let mut entries = Vec::new();
+    for target in targets::registry().all() {
+        let skills_dir = util::get_skills_dir(&target, Scope::Project)?;
+        let wanted = desired.get(&target);
+        for (_, metadata) in read_all_plugin_metadata(&skills_dir) {
+            for skill in metadata.skills {
+                if wanted.is_some_and(|wanted| wanted.contains(&skill)) {
+                    continue;
+                }
+                entries.push(SkillEntry {
+                    path: skills_dir.join(&skill),
+                    name: skill,
+                    target: target.clone(),
+                    scope: Scope::Project,
+                });
+            }
+        }
+    }
+    entries.sort_by(|a, b| a.name.cmp(&b.name).then(a.target.cmp(&b.target)));
+    Ok(entries)
+}
+
+/// Targets named by `--target` filters, or every registered target when none are given.
+fn resolve_target_filter(names: &[String]) -> Result<Vec<Target>> {
+    if names.is_empty() {
+        Ok(targets::registry().all())
+    } else {
+        targets::registry().resolve(names)
+    }
+}
+
+fn update_lockfile(update: impl FnOnce(&mut Lockfile)) -> Result<()> {
+    let path = util::get_lockfile_path();
+    let mut lockfile = Lockfile::load(&path)?.unwrap_or_default();
+    update(&mut lockfile);
+    lockfile.save(&path)
+}
+
+fn handle_install_locked(options: InstallOptions) -> Result<()> {
+    let path = util::get_lockfile_path();
+    let Some(lockfile) = Lockfile::load(&path)? else {
+        return Err(anyhow!("No {} found in the current directory", lockfile::LOCKFILE_NAME));
+    };
+    if options.dry_run {
+        println!("Dry run: no files will be modified.");
+    }
+
+    let clone_cache = CloneCache::new()?;
+    for locked in &lockfile.plugins {
+        let target = targets::registry()
+            .parse(&locked.target)
+            .with_context(|| format!("Invalid entry for {} in {}", locked.name, path.display()))?;
+        let skills_dir = util::get_skills_dir(&target, Scope::Project)?;
+        if is_locked_plugin_installed(&skills_dir, locked) {
+            info!("Plugin {} ({}) matches the lockfile.", locked.name, target);
+            continue;
+        }
+        if options.dry_run {
+            println!("Plugin: {} ({})", locked.name, target);
+            println!("  repo: {} @ {}", locked.url, locked.commit);
+            println!(
+                "  skills: {}",
+                locked
+                    .skills
+                    .iter()
+                    .map(|skill| skill.name.as_str())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            );
+            continue;
+        }
+
+        let location = PluginLocation {
+            url: util::absolute_local_spec(&locked.url)?,
+            subpath: locked.subpath.clone(),
+            git_ref: None,
+            sha: Some(locked.commit.clone()),
+        };
+        let repo_root = clone_cache.checkout(&location, options)?;
+        for skill in &locked.skills {
+            let hash = lockfile::hash_dir(&repo_root.join(&skill.path))?;
+            if hash != skill.hash {
+                return Err(anyhow!(
+                    "Content hash mismatch for skill {} of {} at {}",
+                    skill.name,
+                    locked.name,
+                    locked.commit
+                ));
+            }
+        }
+
+        fs::create_dir_all(&skills_dir).context("Failed to create skills directory")?;
+        remove_legacy_plugin_dir(&skills_dir, &locked.name)?;
+        if let Some(existing) = read_plugin_metadata(&skills_dir, &locked.name) {
+            remove_installed_skills(&skills_dir, &locked.name, &existing)?;
+        }
+        for skill in &locked.skills {
+            println!("Installing skill: {}", skill.name);
+            let dest = skills_dir.join(&skill.name);
+            if dest.exists() {
+                fs::remove_dir_all(&dest).with_context(|| {
+                    format!("Failed to remove existing skill dir for {}", skill.name)
+                })?;
+            }
+            copy_dir_all(&repo_root.join(&skill.path), &dest)?;
+        }
+        let metadata = PluginInstallMetadata {
+            version: locked.version.clone(),
+            skills: locked.skills.iter().map(|skill| skill.name.clone()).collect(),
+            marketplace: Some(locked.marketplace.clone()),
+            source: Some(InstalledSource::from_locked(locked)),
+            hashes: locked_hashes(locked),
+        };
+        write_plugin_metadata(&skills_dir, &locked.name, &metadata)?;
+    }
+
+    Ok(())
+}
+
+fn is_locked_plugin_installed(skills_dir: &Path, locked: &LockedPlugin) -> bool {
+    read_plugin_metadata(skills_dir, &locked.name).is_some()
+        && locked.skills.iter().all(|skill| {
+            lockfile::hash_dir(&skills_dir.join(&skill.name))
+                .map(|hash| hash == skill.hash)
+                .unwrap_or(false)
+        })
+}
+
+fn handle_remove(
+    skills: &[String],
+    plugins: &[String],
+    targets: &[String],
+    scope: Option<Scope>,
+    yes: bool,
+) -> Result<()> {
+    let mut entries = collect_installed_skills(&Scope::selected(scope))?;
+    if !targets.is_empty() {
+        let targets = targets::registry().resolve(targets)?;
+        entries.retain(|entry| targets.contains(&entry.target));
+    }
+    if entries.is_empty() {
+        println!("No skills found to remove.");
+        return Ok(());
+    }
+
+    let selected = if skills.is_empty() && plugins.is_empty() {
+        interactive_select_skills(&entries)?
+    } else {
+        select_skills_by_name(&entries, skills, plugins)?
+    };
+    if selected.is_empty() {
+        println!("No skills selected.");
+        return Ok(());
+    }
+
+    println!("Selected skills:");
+    for entry in &selected {
+        println!("  {} ({})", entry.name, entry.location());
+    }
+
+    if !yes && !confirm_removal(selected.len())? {
+        println!("Cancelled.");
+        return Ok(());
+    }
+
+    remove_skill_entries(&selected)?;
+    println!("Removed {} skill(s).", selected.len());
+    Ok(())
+}
+
+fn remove_skill_entries(entries: &[SkillEntry]) -> Result<()> {
+    let mut removed_by_dir: HashMap<(Target, Scope), HashSet<String>> = HashMap::new();
+    for entry in entries {
+        if entry.path.exists() {
+            fs::remove_dir_all(&entry.path).with_context(|| {
+                format!("Failed to remove skill directory {}", entry.path.display())
+            })?;
+        }
+        removed_by_dir
+            .entry((entry.target.clone(), entry.scope))
+            .or_default()
+            .insert(entry.name.clone());
+    }
+
+    for ((target, scope), removed) in removed_by_dir {
+        let skills_dir = util::get_skills_dir(&target, scope)?;
+        cleanup_metadata(&skills_dir, &removed)?;
+        // Agent home directories (e.g. ~/.codex) hold more than skills; leave them alone.
+        let remove_parent = scope == Scope::Project && !util::project_dir_is_global(&target)?;
+        cleanup_empty_skill_dirs(&skills_dir, remove_parent)?;
+    }
+
+    let project_entries: Vec<&SkillEntry> = entries
+        .iter()
+        .filter(|entry| entry.scope == Scope::Project)
+        .collect();
+    if !project_entries.is_empty() && util::get_lockfile_path().exists() {
+        update_lockfile(|lockfile| {
+            for entry in project_entries {
+                lockfile.remove_skills(entry.target.name(), std::slice::from_ref(&entry.name));
+            }
+        })?;
+    }
+    Ok(())
+}
+
+fn select_skills_by_name(
+    entries: &[SkillEntry],
+    skills: &[String],
+    plugins: &[String],
+) -> Result<Vec<SkillEntry>> {
+    let mut wanted: HashSet<(Scope, Target, String)> = HashSet::new();
+    for entry in entries {
+        if skills.contains(&entry.name) {
+            wanted.insert((entry.scope, entry.target.clone(), entry.name.clone()));
+        }
+    }
+    let missing_skills: Vec<&str> = skills
+        .iter()
+        .filter(|skill| !entries.iter().any(|entry| &entry.name == *skill))
+        .map(|skill| skill.as_str())
+        .collect();
+    if !missing_skills.is_empty() {
+        return Err(anyhow!("Skill(s) not installed: {}", missing_skills.join(", ")));
+    }
+
+    let mut locations: Vec<(Scope, Target)> = entries
+        .iter()
+        .map(|entry| (entry.scope, entry.target.clone()))
+        .collect();
+    locations.sort();
+    locations.dedup();
+    let mut missing_plugins = Vec::new();
+    for plugin in plugins {
+        let mut found = false;
+        for (scope, target) in &locations {
+            let skills_dir = util::get_skills_dir(target, *scope)?;
+            if let Some(metadata) = read_plugin_metadata(&skills_dir, plugin) {
+                found = true;
+                for skill in metadata.skills {
+                    wanted.insert((*scope, target.clone(), skill));
+                }
+            }
+        }
+        if !found {
+            missing_plugins.push(plugin.as_str());
+        }
+    }
+    if !missing_plugins.is_empty() {
+        return Err(anyhow!("Plugin(s) not installed: {}", missing_plugins.join(", ")));
+    }
+
+    Ok(entries
+        .iter()
+        .filter(|entry| wanted.contains(&(entry.scope, entry.target.clone(), entry.name.clone())))
+        .cloned()
+        .collect())
+}
+
+fn handle_list(scope: Option<Scope>, long: bool) -> Result<()> {
+    let entries = collect_installed_skills(&Scope::selected(scope))?;
+    if entries.is_empty() {
+        println!("No skills installed.");
+        return Ok(());
+    }
+    for entry in entries {
+        println!("{} ({})", entry.name, entry.location());
+        if long && let Some(description) = entry.description() {
+            println!("    {}", description);
+        }
+    }
+    Ok(())
+}
+
+fn handle_search(query: &str, scan: bool, options: InstallOptions) -> Result<()> {
+    let searched = searched_marketplaces()?;
+    if searched.is_empty() {
+        return Err(anyhow!(
+            "No marketplaces to search; register one with `skop marketplace add <OWNER/REPO>`"
+        ));
+    }
+    let mut clone_cache = CloneCache::new()?;
+    let mut found = 0;
+    for (name, repo) in &searched {
+        let marketplace = match fetch_marketplace(repo, &mut clone_cache, options) {
+            Ok(marketplace) => marketplace,
+            Err(err) => {
+                warn!("Skipping {}: {:#}", name, err);
+                continue;
+            }
+        };
+        let plan = search_plan(&marketplace, repo, scan, options, &mut clone_cache);
+        for hit in search::search(&marketplace, &plan, query) {
+            let subject = match &hit.skill {
+                Some(skill) => format!("{}/{}", hit.plugin, skill),
+                None => hit.plugin.clone(),
+            };
+            let mut command = format!("skop add {}", name);
+            for skill in &hit.skills {
+                command.push_str(&format!(" --skill {}", skill));
+            }
+            if found > 0 {
+                println!();
+            }
+            println!("{} ({})", subject, name);
+            if let Some(description) = &hit.description {
+                println!("    {}", description);
+            }
+            println!("    {}", command);
+            found += 1;
+        }
+    }
+    if found == 0 {
+        println!("No plugins or skills match {:?}.", query);
+    }
+    Ok(())
+}
+
+/// The marketplaces `skop search` looks in, as (name to show, repository):
+/// registered ones, then those in skop.toml and those installed plugins came from.
+fn searched_marketplaces() -> Result<Vec<(String, String)>> {
+    let mut specs: Vec<String> = marketplaces::registry().keys().cloned().collect();
+    if let Some(manifest) = Manifest::load(&util::get_manifest_path())? {
+        specs.extend(manifest.marketplaces.into_iter().map(|spec| spec.repo));
+    }
+    let installed = collect_installed_plugins(&targets::registry().all(), &Scope::selected(None))?;
+    specs.extend(installed.into_iter().filter_map(|entry| entry.metadata.marketplace));
+
+    let mut searched: Vec<(String, String)> = Vec::new();
+    for spec in specs {
+        let repo = match marketplaces::resolve(&spec) {
+            Ok(repo) => repo,
+            Err(err) => {
+                warn!("Skipping {}: {:#}", spec, err);
+                continue;
+            }
+        };
+        if !searched.iter().any(|(_, known)| *known == repo) {
+            searched.push((spec, repo));
+        }
+    }
+    Ok(searched)
+}
+
+/// The skills to search in a marketplace: those of every plugin found in the
+/// cache (or with `scan`, fetched), so their descriptions can be searched too,
+/// and the declared ones of the rest. A plugin that cannot be resolved, or has
+/// no skills on disk, only loses its own skills.
+fn search_plan(
+    marketplace: &Marketplace,
+    repo: &str,
+    scan: bool,
+    options: InstallOptions,
+    clone_cache: &mut CloneCache,
+) -> SkillPlan {
+    let lookup = InstallOptions {
+        offline: options.offline || !scan,
+        ..options
+    };
+    let (mut plan, errors) = plan_each_plugin(marketplace, repo, PlanMode::Scan, lookup, clone_cache)
+        .unwrap_or_else(|err| (SkillPlan::default(), vec![format!("{:#}", err)]));
+    for error in errors {
+        // Without --scan, plugins that were never fetched are expected.
+        if scan {
+            warn!("Searching only the declared skills of {}", error);
+        } else {
+            info!("Searching only the declared skills of {}", error);
+        }
+    }
+    for plugin in &marketplace.plugins {
+        if plan.by_plugin.get(&plugin.name).is_none_or(|skills| skills.is_empty())
+            && let Some(skills) = declared_skill_names(plugin)
+        {
+            plan.all_skills.extend(skills.iter().cloned());
+            plan.by_plugin.insert(plugin.name.clone(), skills);
+        }
+    }
+    plan
+}
+
+fn handle_info(
+    name: &str,
+    marketplace: Option<&str>,
+    scope: Option<Scope>,
+    options: InstallOptions,
+) -> Result<()> {
+    if let Some(spec) = marketplace {
+        return print_marketplace_info(name, spec, options);
+    }
+    let installed = collect_installed_plugins(&targets::registry().all(), &Scope::selected(scope))?;
+    let lockfile = Lockfile::load(&util::get_lockfile_path())?;
+
+    let plugins: Vec<&InstalledPlugin> = installed.iter().filter(|entry| entry.name == name).collect();
+    if !plugins.is_empty() {
+        println!("Plugin: {}", name);
+        for entry in plugins {
+            let skills_dir = util::get_skills_dir(&entry.target, entry.scope)?;
+            let (source, hashes) = install_record(entry, lockfile.as_ref());
+            println!();
+            println!("{}", format_location(&entry.target, entry.scope));
+            print_fields(&install_fields(entry, source.as_ref()));
+            println!("  Skills:");
+            let width = entry.metadata.skills.iter().map(|skill| skill.len()).max().unwrap_or(0);
+            for skill in &entry.metadata.skills {
+                let dir = skills_dir.join(skill);
+                println!(
+                    "    {:<width$}  {}  {}",
+                    skill,
+                    format_contents(&dir),
+                    skill_state(&dir, hashes.get(skill)),
+                    width = width
+                );
+            }
+        }
+        return Ok(());
+    }
+
+    let owners: Vec<&InstalledPlugin> = installed
+        .iter()
+        .filter(|entry| entry.metadata.skills.iter().any(|skill| skill == name))
+        .collect();
+    if owners.is_empty() {
+        return Err(anyhow!(
+            "{} is not installed; use --marketplace <REPO> to look it up in a marketplace",
+            name
+        ));
+    }
+    println!("Skill: {}", name);
+    for entry in owners {
+        let dir = util::get_skills_dir(&entry.target, entry.scope)?.join(name);
+        let (source, hashes) = install_record(entry, lockfile.as_ref());
+        let mut fields = vec![("Plugin", entry.name.clone())];
+        fields.extend(install_fields(entry, source.as_ref()));
+        fields.push((
+            "Description",
+            skill::description(&dir).unwrap_or_else(|| "-".to_string()),
+        ));
+        fields.push(("Path", dir.display().to_string()));
+        fields.push(("Contents", format_contents(&dir)));
+        fields.push(("Status", skill_state(&dir, hashes.get(name)).to_string()));
+        println!();
+        println!("{}", format_location(&entry.target, entry.scope));
+        print_fields(&fields);
+    }
+    Ok(())
+}
 
-        let installed_skills = install_plugin(
-            plugin,
-            &skills_dir,
-            repo,
-            plugin_root,
-            options,
-            Some(&selected_for_plugin),
-            clone_cache,
-        )?;
+/// Looks `name` up as a plugin, then as a skill, in the marketplace `repo`.
+fn print_marketplace_info(name: &str, spec: &str, options: InstallOptions) -> Result<()> {
+    let repo = &marketplaces::resolve(spec)?;
+    let mut clone_cache = CloneCache::new()?;
+    let mut marketplace = fetch_marketplace(repo, &mut clone_cache, options)?;
+    let is_plugin = marketplace.plugins.iter().any(|plugin| plugin.name == name);
+    if is_plugin {
+        // No need to resolve the skills of every other plugin.
+        marketplace.plugins.retain(|plugin| plugin.name == name);
+    }
+    let plan = plan_marketplace_skills(&marketplace, repo, PlanMode::Declared, options, &mut clone_cache)?;
+    let owner = marketplace.plugins.iter().find(|plugin| {
+        is_plugin
+            || plan
+                .by_plugin
+                .get(&plugin.name)
+                .is_some_and(|skills| skills.iter().any(|skill| skill == name))
+    });
+    let Some(owner) = owner.map(|plugin| plugin.name.clone()) else {
+        return Err(anyhow!("{} is not a plugin or skill in {}", name, repo));
+    };
+    marketplace.plugins.retain(|plugin| plugin.name == owner);
+    let plugin = &marketplace.plugins[0];
+    let plugin_root = marketplace
+        .metadata
+        .as_ref()
+        .and_then(|metadata| metadata.plugin_root.as_deref());
+    let location = resolve_plugin_url(plugin, repo, plugin_root);
+    let skills = plan.by_plugin.get(&plugin.name).cloned().unwrap_or_default();
 
-        if options.dry_run {
-            println!(
-                "  skills: {}",
-                if installed_skills.is_empty() {
-                    "none".to_string()
-                } else {
-                    installed_skills.join(", ")
+    let mut fields = Vec::new();
+    let description = if is_plugin {
+        println!("Plugin: {}", name);
+        plugin.description.clone()
+    } else {
+        println!("Skill: {}", name);
+        fields.push(("Plugin", plugin.name.clone()));
+        match plan.descriptions.get(name) {
+            Some(description) => Some(description.clone()),
+            // Declared skills are planned without fetching; read the SKILL.md from the one plugin.
+            None => match plan_marketplace_skills(&marketplace, repo, PlanMode::Scan, options, &mut clone_cache) {
+                Ok(scanned) => scanned.descriptions.get(name).cloned(),
+                Err(err) => {
+                    warn!("Could not read the description of {}: {:#}", name, err);
+                    None
                 }
-            );
-            continue;
+            },
         }
-
-        let new_metadata = PluginInstallMetadata {
-            version: plugin.version.clone(),
-            skills: installed_skills.clone(),
-        };
-        write_plugin_metadata(&skills_dir, &plugin.name, &new_metadata)?;
-        info!(
-            "Installed {} ({} skill(s))",
-            plugin.name,
-            installed_skills.len()
-        );
+    };
+    fields.push(("Version", plugin.version.clone().unwrap_or_else(|| "-".to_string())));
+    fields.push(("Marketplace", spec.to_string()));
+    fields.push(("Description", description.unwrap_or_else(|| "-".to_string())));
+    fields.push(("Source", format_source(&location.url, location.subpath.as_deref())));
+    if let Some(git_ref) = location.git_ref {
+        fields.push(("Ref", git_ref));
     }
+    if let Some(sha) = location.sha {
+        fields.push(("Commit", sha));
+    }
+    if is_plugin {
+        fields.push(("Skills", skills.join(", ")));
+    }
+    print_fields(&fields);
 
+    let selected: Vec<&str> = if is_plugin {
+        skills.iter().map(|skill| skill.as_str()).collect()
+    } else {
+        vec![name]
+    };
+    let mut command = format!("skop add {}", spec);
+    for skill in selected {
+        command.push_str(&format!(" --skill {}", skill));
+    }
+    println!();
+    println!("Install with: {}", command);
     Ok(())
 }
 
-fn handle_remove() -> Result<()> {
-    let entries = collect_installed_skills()?;
-    if entries.is_empty() {
-        println!("No skills found to remove.");
-        return Ok(());
+/// Where an install came from and the hashes of its skills. Project installs
+/// made before skop recorded these in the plugin metadata have them in skop.lock.
+fn install_record(
+    entry: &InstalledPlugin,
+    lockfile: Option<&Lockfile>,
+) -> (Option<InstalledSource>, BTreeMap<String, String>) {
+    let metadata = &entry.metadata;
+    if metadata.source.is_some() || !metadata.hashes.is_empty() || entry.scope != Scope::Project {
+        return (metadata.source.clone(), metadata.hashes.clone());
     }
+    let target = entry.target.to_string();
+    match lockfile.and_then(|lockfile| {
+        lockfile
+            .plugins
+            .iter()
+            .find(|locked| locked.name == entry.name && locked.target == target)
+    }) {
+        Some(locked) => (Some(InstalledSource::from_locked(locked)), locked_hashes(locked)),
+        None => (None, BTreeMap::new()),
+    }
+}
 
-    let selected = interactive_select_skills(&entries)?;
-    if selected.is_empty() {
-        println!("No skills selected.");
-        return Ok(());
+fn locked_hashes(locked: &LockedPlugin) -> BTreeMap<String, String> {
+    locked
+        .skills
+        .iter()
+        .map(|skill| (skill.name.clone(), skill.hash.clone()))
+        .collect()
+}
+
+fn install_fields(
+    entry: &InstalledPlugin,
+    source: Option<&InstalledSource>,
+) -> Vec<(&'static str, String)> {
+    let mut fields = vec![
+        (
+            "Version",
+            entry.metadata.version.clone().unwrap_or_else(|| "-".to_string()),
+        ),
+        (
+            "Marketplace",
+            entry.metadata.marketplace.clone().unwrap_or_else(|| "-".to_string()),
+        ),
+    ];
+    match source {
+        Some(source) => {
+            fields.push(("Source", format_source(&source.url, source.subpath.as_deref())));
+            if let Some(git_ref) = &source.git_ref {
+                fields.push(("Ref", git_ref.clone()));
+            }
+            fields.push(("Commit", source.commit.clone()));
+        }
+        None => fields.push(("Source", "-".to_string())),
     }
+    fields
+}
 
-    println!("Selected skills:");
-    for entry in &selected {
-        println!("  {} ({})", entry.name, entry.target);
+fn format_source(url: &str, subpath: Option<&str>) -> String {
+    match subpath {
+        Some(subpath) => format!("{} ({})", url, subpath),
+        None => url.to_string(),
     }
+}
 
-    if !confirm_removal(selected.len())? {
-        println!("Cancelled.");
-        return Ok(());
+/// Prints `label: value` lines with the values lined up.
+fn print_fields(fields: &[(&str, String)]) {
+    let width = fields.iter().map(|(label, _)| label.len() + 1).max().unwrap_or(0);
+    for (label, value) in fields {
+        println!("  {:<width$}  {}", format!("{}:", label), value, width = width);
     }
+}
 
-    let mut removed_by_dir: HashMap<PathBuf, HashSet<String>> = HashMap::new();
-    for entry in &selected {
-        if entry.path.exists() {
-            fs::remove_dir_all(&entry.path).with_context(|| {
-                format!("Failed to remove skill directory {}", entry.path.display())
-            })?;
+/// Compares an installed skill with the hash recorded when it was installed.
+fn skill_state(dir: &Path, expected: Option<&String>) -> &'static str {
+    if !dir.is_dir() {
+        return "missing";
+    }
+    let Some(expected) = expected else {
+        return "unknown";
+    };
+    match lockfile::hash_dir(dir) {
+        Ok(hash) if &hash == expected => "unmodified",
+        Ok(_) => "modified",
+        Err(err) => {
+            info!("Failed to hash {}: {:#}", dir.display(), err);
+            "unknown"
         }
-        let skills_dir = util::get_skills_dir(entry.target);
-        removed_by_dir
-            .entry(skills_dir)
-            .or_default()
-            .insert(entry.name.clone());
     }
+}
 
-    for (skills_dir, removed) in removed_by_dir {
-        cleanup_metadata(&skills_dir, &removed)?;
-        cleanup_empty_skill_dirs(&skills_dir)?;
+fn format_contents(dir: &Path) -> String {
+    match count_files(dir) {
+        Ok((files, size)) => format!("{} file(s), {}", files, cache::format_size(size)),
+        Err(_) => "-".to_string(),
     }
-
-    println!("Removed {} skill(s).", selected.len());
-    Ok(())
 }
 
-fn handle_list() -> Result<()> {
-    let entries = collect_installed_skills()?;
-    if entries.is_empty() {
-        println!("No skills installed.");
-        return Ok(());
-    }
-    for entry in entries {
-        println!("{} ({})", entry.name, entry.target);
+fn count_files(dir: &Path) -> Result<(usize, u64)> {
+    let (mut files, mut size) = (0, 0);
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let file_type = entry.file_type()?;
+        if file_type.is_dir() {
+            let (nested_files, nested_size) = count_files(&entry.path())?;
+            files += nested_files;
+            size += nested_size;
+        } else if file_type.is_file() {
+            files += 1;
+            size += entry.metadata()?.len();
+        }
     }
-    Ok(())
+    Ok((files, size))
 }
 
-fn fetch_marketplace(repo: &str) -> Result<Marketplace> {
-    let url = util::get_marketplace_url(repo);
+fn fetch_marketplace(
+    spec: &str,
+    clone_cache: &mut CloneCache,
+    options: InstallOptions,
+) -> Result<Marketplace> {
+    if let Some(dir) = util::local_marketplace_path(spec) {
+        let marketplace = load_marketplace_file(&dir)?;
+        info!("Found marketplace: {}", marketplace.name);
+        return Ok(marketplace);
+    }
+    if options.offline {
+        let content = clone_cache.store.load_marketplace(spec).ok_or_else(|| {
+            anyhow!(
+                "Marketplace {} is not in the cache; run without --offline once to fetch it",
+                spec
+            )
+        })?;
+        let marketplace: Marketplace = serde_json::from_str(&content)
+            .with_context(|| format!("Failed to parse cached marketplace for {}", spec))?;
+        info!("Found cached marketplace: {}", marketplace.name);
+        return Ok(marketplace);
+    }
+    let (repo, git_ref) = util::split_marketplace_ref(spec);
+    if util::is_git_url(repo) {
+        // Other hosts have no raw endpoint we can rely on; the clone is reused
+        // later by plugins that live in the marketplace repo.
+        let location = PluginLocation {
+            url: repo.to_string(),
+            subpath: None,
+            git_ref: git_ref.map(|value| value.to_string()),
+            sha: None,
+        };
+        let repo_root = clone_cache.checkout(&location, options)?;
+        let marketplace = load_marketplace_file(&repo_root)
+            .with_context(|| format!("Failed to read marketplace from {}", repo))?;
+        info!("Found marketplace: {}", marketplace.name);
+        save_marketplace(clone_cache, spec, &serde_json::to_string_pretty(&marketplace)?);
+        return Ok(marketplace);
+    }
+    let git_ref = match git_ref {
+        Some(git_ref) => git_ref.to_string(),
+        None => detect_default_branch(&resolve_marketplace_repo_url(repo))?,
+    };
+    let url = util::get_marketplace_url(repo, &git_ref);
     info!("Fetching marketplace from {}", url);
 
     let resp = reqwest::blocking::get(&url)?;
@@ -342,24 +2528,92 @@ fn fetch_marketplace(repo: &str) -> Result<Marketplace> {
         ));
     }
 
-    let marketplace: Marketplace = resp.json()?;
+    let content = resp.text()?;
+    let marketplace: Marketplace = serde_json::from_str(&content)
+        .with_context(|| format!("Failed to parse marketplace from {}", url))?;
     info!("Found marketplace: {}", marketplace.name);
+    save_marketplace(clone_cache, spec, &content);
     Ok(marketplace)
 }
 
+/// Keeps a fetched marketplace for `--offline`; failing to do so only costs that.
+fn save_marketplace(clone_cache: &CloneCache, spec: &str, content: &str) {
+    if let Err(err) = clone_cache.store.save_marketplace(spec, content) {
+        warn!("Could not cache marketplace {}: {:#}", spec, err);
+    }
+}
+
+/// Asks the remote which branch `HEAD` points to, so marketplaces on `master` etc. resolve.
+fn detect_default_branch(git_url: &str) -> Result<String> {
+    let output = run_git(Path::new("."), &["ls-remote", "--symref", git_url, "HEAD"])
+        .with_context(|| format!("Failed to detect the default branch of {}", git_url))?;
+    parse_symref_head(&output)
+        .ok_or_else(|| anyhow!("Could not determine the default branch of {}", git_url))
+}
+
+fn parse_symref_head(ls_remote: &str) -> Option<String> {
+    ls_remote.lines().find_map(|line| {
+        let (symref, name) = line.strip_prefix("ref: ")?.split_once('\t')?;
+        if name.trim() != "HEAD" {
+            return None;
+        }
+        Some(symref.strip_prefix("refs/heads/").unwrap_or(symref).to_string())
+    })
+}
+
+#[derive(Default)]
 struct SkillPlan {
     by_plugin: HashMap<String, Vec<String>>,
     all_skills: Vec<String>,
+    /// Descriptions of the skills that were fetched while planning.
+    descriptions: HashMap<String, String>,
+}
+
+/// How [`plan_marketplace_skills`] finds the skills of each plugin.
+#[derive(Copy, Clone, PartialEq, Eq)]
+enum PlanMode {
+    /// Use the skills a plugin declares in marketplace.json; only plugins that
+    /// declare none are fetched.
+    Declared,
+    /// Fetch every plugin and discover its skills on disk.
+    Scan,
 }
 
 fn plan_marketplace_skills(
     marketplace: &Marketplace,
     repo: &str,
+    mode: PlanMode,
     options: InstallOptions,
     clone_cache: &mut CloneCache,
 ) -> Result<SkillPlan> {
+    let (plan, errors) = plan_each_plugin(marketplace, repo, mode, options, clone_cache)?;
+    if !errors.is_empty() {
+        return Err(anyhow!(
+            "Failed to resolve {} plugin(s):\n  {}{}",
+            errors.len(),
+            errors.join("\n  "),
+            if options.offline {
+                "\nRun without --offline once to fetch them."
+            } else {
+                ""
+            }
+        ));
+    }
+    Ok(plan)
+}
+
+/// Like [`plan_marketplace_skills`], but a plugin that cannot be resolved is
+/// left out of the plan and reported as `plugin: error`.
+fn plan_each_plugin(
+    marketplace: &Marketplace,
+    repo: &str,
+    mode: PlanMode,
+    options: InstallOptions,
+    clone_cache: &mut CloneCache,
+) -> Result<(SkillPlan, Vec<String>)> {
     let mut by_plugin = HashMap::new();
     let mut all = HashSet::new();
+    let mut descriptions = HashMap::new();
     let plugin_root = marketplace
         .metadata
         .as_ref()
@@ -370,10 +2624,36 @@ fn plan_marketplace_skills(
         dry_run: true,
         max_depth: options.max_depth,
         quiet: true,
+        scope: options.scope,
+        fetch: options.fetch,
+        jobs: options.jobs,
+        offline: options.offline,
     };
 
+    let mut scanned = Vec::new();
     for plugin in &marketplace.plugins {
-        let skills = install_plugin(
+        let declared = match mode {
+            PlanMode::Declared => declared_skill_names(plugin),
+            PlanMode::Scan => None,
+        };
+        match declared {
+            Some(skills) => {
+                all.extend(skills.iter().cloned());
+                by_plugin.insert(plugin.name.clone(), skills);
+            }
+            None => scanned.push(plugin),
+        }
+    }
+
+    let failures = prefetch_plugins(&scanned, repo, plugin_root, plan_options, clone_cache);
+    let mut errors = Vec::new();
+    for plugin in scanned {
+        let location = resolve_plugin_url(plugin, repo, plugin_root);
+        if let Some(err) = failures.get(&source_key(&location)) {
+            errors.push(format!("{}: {}", plugin.name, err));
+            continue;
+        }
+        let result = install_plugin(
             plugin,
             temp_skills_dir.path(),
             repo,
@@ -381,7 +2661,17 @@ fn plan_marketplace_skills(
             plan_options,
             None,
             clone_cache,
-        )?;
+        );
+        let skills = match result {
+            Ok(install) => {
+                descriptions.extend(install.descriptions);
+                install.skills
+            }
+            Err(err) => {
+                errors.push(format!("{}: {:#}", plugin.name, err));
+                continue;
+            }
+        };
         for skill in &skills {
             all.insert(skill.clone());
         }
@@ -390,17 +2680,71 @@ fn plan_marketplace_skills(
 
     let mut all_skills: Vec<String> = all.into_iter().collect();
     all_skills.sort();
-    Ok(SkillPlan { by_plugin, all_skills })
+    let plan = SkillPlan {
+        by_plugin,
+        all_skills,
+        descriptions,
+    };
+    Ok((plan, errors))
+}
+
+/// Identifies a repository at a ref or commit, regardless of subpath.
+fn source_key(location: &PluginLocation) -> String {
+    match (&location.sha, &location.git_ref) {
+        (Some(sha), _) => format!("{}@{}", location.url, sha),
+        (None, Some(r)) => format!("{}#{}", location.url, r),
+        (None, None) => location.url.clone(),
+    }
+}
+
+/// Fetches the distinct sources of `plugins` on up to `options.jobs` threads, so
+/// that discovery afterwards is served from `clone_cache`. Returns the error of
+/// each source that could not be fetched, keyed by [`source_key`].
+fn prefetch_plugins(
+    plugins: &[&model::PluginEntry],
+    marketplace_repo: &str,
+    plugin_root: Option<&str>,
+    options: InstallOptions,
+    clone_cache: &CloneCache,
+) -> HashMap<String, String> {
+    let mut locations: Vec<PluginLocation> = Vec::new();
+    for plugin in plugins {
+        let location = resolve_plugin_url(plugin, marketplace_repo, plugin_root);
+        match locations
+            .iter_mut()
+            .find(|existing| source_key(existing) == source_key(&location))
+        {
+            // A full download serves every subpath of the repository.
+            Some(existing) if existing.subpath != location.subpath => existing.subpath = None,
+            Some(_) => {}
+            None => locations.push(location),
+        }
+    }
+
+    let next = AtomicUsize::new(0);
+    let failures = Mutex::new(HashMap::new());
+    let workers = options.jobs.clamp(1, locations.len().max(1));
+    thread::scope(|scope| {
+        for _ in 0..workers {
+            scope.spawn(|| {
+                while let Some(location) = locations.get(next.fetch_add(1, Ordering::Relaxed)) {
+                    if let Err(err) = clone_cache.checkout(location, options) {
+                        failures
+                            .lock()
+                            .unwrap()
+                            .insert(source_key(location), format!("{:#}", err));
+                    }
+                }
+            });
+        }
+    });
+    failures.into_inner().unwrap()
 }
 
 fn select_targets() -> Result<Vec<Target>> {
-    let targets = vec![
-        Target::All,
-        Target::Codex,
-        Target::Opencode,
-        Target::Antigravity,
-    ];
-    let labels: Vec<String> = targets.iter().map(|t| t.to_string()).collect();
+    let targets = targets::registry().all();
+    let mut labels = vec![targets::ALL.to_string()];
+    labels.extend(targets.iter().map(|t| t.to_string()));
     let preselected = vec![false; labels.len()];
     let selected = interactive_select_labels(
         "Select targets (space: toggle, ↑/↓: move, enter: confirm, q: quit)",
@@ -408,27 +2752,56 @@ fn select_targets() -> Result<Vec<Target>> {
         &preselected,
         true,
     )?;
-    if let Some(all_index) = targets.iter().position(|t| *t == Target::All) {
-        if selected.get(all_index).copied().unwrap_or(false) {
-            return Ok(vec![Target::Codex, Target::Opencode, Target::Antigravity]);
-        }
+    if selected.first().copied().unwrap_or(false) {
+        return Ok(targets);
     }
     let chosen = targets
         .into_iter()
-        .zip(selected.into_iter())
-        .filter_map(|(target, is_selected)| if is_selected && target != Target::All { Some(target) } else { None })
+        .zip(selected.into_iter().skip(1))
+        .filter_map(|(target, is_selected)| if is_selected { Some(target) } else { None })
         .collect();
     Ok(chosen)
 }
 
+fn resolve_requested_skills(plan: &SkillPlan, requested: &[String]) -> Result<HashSet<String>> {
+    if requested.is_empty() {
+        return Ok(plan.all_skills.iter().cloned().collect());
+    }
+    let unknown: Vec<&str> = requested
+        .iter()
+        .filter(|skill| !plan.all_skills.contains(*skill))
+        .map(|skill| skill.as_str())
+        .collect();
+    if !unknown.is_empty() {
+        return Err(anyhow!(
+            "Unknown skill(s): {}. Available skills: {}",
+            unknown.join(", "),
+            if plan.all_skills.is_empty() {
+                "none".to_string()
+            } else {
+                plan.all_skills.join(", ")
+            }
+        ));
+    }
+    Ok(requested.iter().cloned().collect())
+}
+
 fn select_skills(plan: &SkillPlan) -> Result<HashSet<String>> {
     if plan.all_skills.is_empty() {
         return Ok(HashSet::new());
     }
     let preselected = vec![true; plan.all_skills.len()];
+    let labels: Vec<String> = plan
+        .all_skills
+        .iter()
+        .map(|skill| match plan.descriptions.get(skill) {
+            Some(description) => format!("{} — {}", skill, description),
+            None => skill.clone(),
+        })
+        .collect();
     let selected = interactive_select_labels(
         "Select skills to install (space: toggle, ↑/↓: move, enter: confirm, q: quit)",
-        &plan.all_skills,
+        &labels,
         &preselected,
         true,
     )?;
@@ -436,41 +2809,64 @@ fn select_skills(plan: &SkillPlan) -> Result<HashSet<String>> {
         .all_skills
         .iter()
         .cloned()
-        .zip(selected.into_iter())
+        .zip(selected)
         .filter_map(|(skill, is_selected)| if is_selected { Some(skill) } else { None })
         .collect();
     Ok(chosen)
 }
 
-fn collect_installed_skills() -> Result<Vec<SkillEntry>> {
+/// A project skills directory that is also the global one is visited once, as global.
+fn is_listed_as_global(target: &Target, scope: Scope, scopes: &[Scope]) -> Result<bool> {
+    Ok(scope == Scope::Project
+        && scopes.contains(&Scope::Global)
+        && util::project_dir_is_global(target)?)
+}
+
+fn collect_installed_skills(scopes: &[Scope]) -> Result<Vec<SkillEntry>> {
     let mut entries = Vec::new();
-    for target in [Target::Codex, Target::Opencode, Target::Antigravity] {
-        let skills_dir = util::get_skills_dir(target);
-        if !skills_dir.exists() {
-            continue;
-        }
-        for entry in fs::read_dir(&skills_dir)? {
-            let entry = entry?;
-            let path = entry.path();
-            let name = entry.file_name();
-            let Some(name_str) = name.to_str() else { continue };
-            if name_str == ".skop" {
+    for scope in scopes {
+        for target in targets::registry().all_in(*scope) {
+            if is_listed_as_global(&target, *scope, scopes)? {
                 continue;
             }
-            if entry.file_type()?.is_dir() && path.join("SKILL.md").is_file() {
-                entries.push(SkillEntry {
-                    name: name_str.to_string(),
-                    path,
-                    target,
-                });
+            let skills_dir = util::get_skills_dir(&target, *scope)?;
+            if !skills_dir.exists() {
+                continue;
+            }
+            for entry in fs::read_dir(&skills_dir)? {
+                let entry = entry?;
+                let path = entry.path();
+                let name = entry.file_name();
+                let Some(name_str) = name.to_str() else { continue };
+                if name_str == ".skop" {
+                    continue;
+                }
+                if entry.file_type()?.is_dir() && path.join("SKILL.md").is_file() {
+                    entries.push(SkillEntry {
+                        name: name_str.to_string(),
+                        path,
+                        target: target.clone(),
+                        scope: *scope,
+                    });
+                }
             }
         }
     }
-    entries.sort_by(|a, b| a.name.cmp(&b.name).then(a.target.cmp(&b.target)));
+    entries.sort_by(|a, b| {
+        a.name
+            .cmp(&b.name)
+            .then(a.scope.cmp(&b.scope))
+            .then(a.target.cmp(&b.target))
+    });
     Ok(entries)
 }
 
 fn interactive_select_skills(entries: &[SkillEntry]) -> Result<Vec<SkillEntry>> {
+    let mut labels = vec!["all".to_string()];
+    labels.extend(entries.iter().map(|entry| match entry.description() {
+        Some(description) => format!("{} ({}) — {}", entry.name, entry.location(), description),
+        None => format!("{} ({})", entry.name, entry.location()),
+    }));
     let mut selected = vec![false; entries.len() + 1];
     let mut index = 0usize;
     let mut stdout = io::stdout();
@@ -478,17 +2874,15 @@ fn interactive_select_skills(entries: &[SkillEntry]) -> Result<Vec<SkillEntry>>
     let mut status: Option<String> = None;
 
     loop {
-        render_skill_list(&mut stdout, entries, &selected, index, status.as_deref())?;
-        match event::read()? {
-            event::Event::Key(key) => match key.code {
+        render_skill_list(&mut stdout, &labels, &selected, index, status.as_deref())?;
+        if let event::Event::Key(key) = event::read()? {
+            match key.code {
                 event::KeyCode::Char('q') | event::KeyCode::Esc => {
                     execute!(stdout, terminal::Clear(terminal::ClearType::All))?;
                     return Ok(Vec::new());
                 }
                 event::KeyCode::Up => {
-                    if index > 0 {
-                        index -= 1;
-                    }
+                    index = index.saturating_sub(1);
                     status = None;
                 }
                 event::KeyCode::Down => {
@@ -500,9 +2894,7 @@ fn interactive_select_skills(entries: &[SkillEntry]) -> Result<Vec<SkillEntry>>
                 event::KeyCode::Char(' ') => {
                     if index == 0 {
                         let new_state = !selected[0];
-                        for state in &mut selected {
-                            *state = new_state;
-                        }
+                        selected.fill(new_state);
                     } else if let Some(state) = selected.get_mut(index) {
                         *state = !*state;
                         selected[0] = selected.iter().skip(1).all(|value| *value);
@@ -517,8 +2909,7 @@ fn interactive_select_skills(entries: &[SkillEntry]) -> Result<Vec<SkillEntry>>
                     break;
                 }
                 _ => {}
-            },
-            _ => {}
+            }
         }
     }
 
@@ -551,16 +2942,14 @@ fn interactive_select_labels(
 
     loop {
         render_label_list(&mut stdout, title, labels, &selected, index, status.as_deref())?;
-        match event::read()? {
-            event::Event::Key(key) => match key.code {
+        if let event::Event::Key(key) = event::read()? {
+            match key.code {
                 event::KeyCode::Char('q') | event::KeyCode::Esc => {
                     execute!(stdout, terminal::Clear(terminal::ClearType::All))?;
                     return Ok(vec![false; labels.len()]);
                 }
                 event::KeyCode::Up => {
-                    if index > 0 {
-                        index -= 1;
-                    }
+                    index = index.saturating_sub(1);
                     status = None;
                 }
                 event::KeyCode::Down => {
@@ -583,8 +2972,7 @@ fn interactive_select_labels(
                     break;
                 }
                 _ => {}
-            },
-            _ => {}
+            }
         }
     }
 
@@ -652,7 +3040,7 @@ fn print_progress(message: &str) -> Result<()> {
 
 fn render_skill_list(
     stdout: &mut io::Stdout,
-    entries: &[SkillEntry],
+    labels: &[String],
     selected: &[bool],
     index: usize,
     status: Option<&str>,
@@ -686,12 +3074,7 @@ fn render_skill_list(
     };
     let end = usize::min(start + available, total);
     for (row, idx) in (start..end).enumerate() {
-        let line = if idx == 0 {
-            "all".to_string()
-        } else {
-            let entry = &entries[idx - 1];
-            format!("{} ({})", entry.name, entry.target)
-        };
+        let line = &labels[idx];
         let cursor = if idx == index { ">" } else { " " };
         let mark = if selected.get(idx).copied().unwrap_or(false) {
             "x"
@@ -757,52 +3140,83 @@ fn confirm_removal(count: usize) -> Result<bool> {
     Ok(matches!(input.trim(), "y" | "Y"))
 }
 
-fn should_install_plugin(
+#[derive(Debug, PartialEq, Eq)]
+enum VersionStatus {
+    NotInstalled,
+    Outdated,
+    UpToDate,
+    Unparsable,
+    MissingInstalledVersion,
+    MissingMarketplaceVersion,
+}
+
+fn compare_plugin_version(
     plugin: &model::PluginEntry,
     metadata: Option<&PluginInstallMetadata>,
-) -> bool {
+) -> VersionStatus {
     let Some(metadata) = metadata else {
-        info!("Installing new plugin: {}", plugin.name);
-        return true;
+        return VersionStatus::NotInstalled;
     };
-
-    match &plugin.version {
-        Some(new_version) => match &metadata.version {
-            Some(curr_version) => {
-                match (
-                    semver::Version::parse(curr_version),
-                    semver::Version::parse(new_version),
-                ) {
-                    (Ok(v_curr), Ok(v_new)) => {
-                        if v_new > v_curr {
-                            info!(
-                                "Updating {} from {} to {}",
-                                plugin.name, curr_version, new_version
-                            );
-                            true
-                        } else {
-                            info!("Plugin {} is up to date ({}).", plugin.name, curr_version);
-                            false
-                        }
-                    }
-                    _ => {
-                        warn!(
-                            "Version parse failed for {}, reinstalling to be safe.",
-                            plugin.name
-                        );
-                        true
-                    }
-                }
-            }
-            None => {
-                info!(
-                    "Plugin {} exists but no version in metadata, updating.",
-                    plugin.name
-                );
-                true
+    let Some(new_version) = &plugin.version else {
+        return VersionStatus::MissingMarketplaceVersion;
+    };
+    let Some(curr_version) = &metadata.version else {
+        return VersionStatus::MissingInstalledVersion;
+    };
+    match (
+        semver::Version::parse(curr_version),
+        semver::Version::parse(new_version),
+    ) {
+        (Ok(v_curr), Ok(v_new)) => {
+            if v_new > v_curr {
+                VersionStatus::Outdated
+            } else {
+                VersionStatus::UpToDate
             }
-        },
-        None => {
+        }
+        _ => VersionStatus::Unparsable,
+    }
+}
+
+fn should_install_plugin(
+    plugin: &model::PluginEntry,
+    metadata: Option<&PluginInstallMetadata>,
+) -> bool {
+    let curr_version = metadata
+        .and_then(|metadata| metadata.version.as_deref())
+        .unwrap_or_default();
+    let new_version = plugin.version.as_deref().unwrap_or_default();
+    match compare_plugin_version(plugin, metadata) {
+        VersionStatus::NotInstalled => {
+            info!("Installing new plugin: {}", plugin.name);
+            true
+        }
+        VersionStatus::Outdated => {
+            info!(
+                "Updating {} from {} to {}",
+                plugin.name, curr_version, new_version
+            );
+            true
+        }
+        VersionStatus::UpToDate => {
+            info!("Plugin {} is up to date ({}).", plugin.name, curr_version);
+            false
+        }
+        VersionStatus::Unparsable => {
+            warn!(
+                "Version parse failed for {}, reinstalling to be safe.",
+                plugin.name
+            );
+            true
+        }
+        VersionStatus::MissingInstalledVersion => {
+            info!(
+                "Plugin {} exists but no version in metadata, updating.",
+                plugin.name
+            );
+            true
+        }
+        VersionStatus::MissingMarketplaceVersion => {
             info!(
                 "Plugin {} has no version in marketplace, updating.",
                 plugin.name
@@ -828,6 +3242,26 @@ fn read_plugin_metadata(skills_dir: &Path, plugin_name: &str) -> Option<PluginIn
     serde_json::from_str(&content).ok()
 }
 
+fn read_all_plugin_metadata(skills_dir: &Path) -> Vec<(String, PluginInstallMetadata)> {
+    let Ok(entries) = fs::read_dir(skills_dir.join(".skop")) else {
+        return Vec::new();
+    };
+    let mut plugins: Vec<(String, PluginInstallMetadata)> = entries
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let path = entry.path();
+            if path.extension().and_then(|ext| ext.to_str()) != Some("json") {
+                return None;
+            }
+            let name = path.file_stem()?.to_str()?.to_string();
+            let content = fs::read_to_string(&path).ok()?;
+            Some((name, serde_json::from_str(&content).ok()?))
+        })
+        .collect();
+    plugins.sort_by(|a, b| a.0.cmp(&b.0));
+    plugins
+}
+
 fn write_plugin_metadata(
     skills_dir: &Path,
     plugin_name: &str,
@@ -884,7 +3318,7 @@ fn cleanup_metadata(skills_dir: &Path, removed_skills: &HashSet<String>) -> Resu
     Ok(())
 }
 
-fn cleanup_empty_skill_dirs(skills_dir: &Path) -> Result<()> {
+fn cleanup_empty_skill_dirs(skills_dir: &Path, remove_parent: bool) -> Result<()> {
     if !skills_dir.exists() {
         return Ok(());
     }
@@ -896,10 +3330,12 @@ fn cleanup_empty_skill_dirs(skills_dir: &Path) -> Result<()> {
 
     if is_dir_empty(skills_dir)? {
         fs::remove_dir(skills_dir)?;
-        if let Some(parent) = skills_dir.parent() {
-            if parent.exists() && is_dir_empty(parent)? {
-                fs::remove_dir(parent)?;
-            }
+        if remove_parent
+            && let Some(parent) = skills_dir.parent()
+            && parent.exists()
+            && is_dir_empty(parent)?
+        {
+            fs::remove_dir(parent)?;
         }
     }
 
@@ -938,7 +3374,7 @@ fn install_plugin(
     options: InstallOptions,
     selected_skills: Option<&HashSet<String>>,
     clone_cache: &mut CloneCache,
-) -> Result<Vec<String>> {
+) -> Result<PluginInstall> {
     let mut visited = HashSet::new();
     install_plugin_recursive(
         plugin,
@@ -953,6 +3389,7 @@ fn install_plugin(
     )
 }
 
+#[allow(clippy::too_many_arguments)]
 fn install_plugin_recursive(
     plugin: &model::PluginEntry,
     skills_dir: &Path,
@@ -963,7 +3400,7 @@ fn install_plugin_recursive(
     options: InstallOptions,
     selected_skills: Option<&HashSet<String>>,
     clone_cache: &mut CloneCache,
-) -> Result<Vec<String>> {
+) -> Result<PluginInstall> {
     if depth > options.max_depth {
         return handle_missing_skills(
             options,
@@ -974,12 +3411,8 @@ fn install_plugin_recursive(
         );
     }
 
-    let (git_url, subpath, git_ref) = resolve_plugin_url(plugin, marketplace_repo, plugin_root);
-    let visit_key = match &git_ref {
-        Some(r) => format!("{}#{}", git_url, r),
-        None => git_url.clone(),
-    };
-    if !visited.insert(visit_key) {
+    let location = resolve_plugin_url(plugin, marketplace_repo, plugin_root);
+    if !visited.insert(source_key(&location)) {
         return handle_missing_skills(
             options,
             &format!(
@@ -989,7 +3422,13 @@ fn install_plugin_recursive(
         );
     }
 
-    let repo_root = clone_cache.get_or_clone(&git_url, git_ref.as_deref(), options)?;
+    let repo_root = clone_cache.checkout(&location, options)?;
+    let PluginLocation {
+        url: git_url,
+        subpath,
+        git_ref,
+        sha: _,
+    } = location;
     if options.dry_run && !options.quiet {
         let indent = "  ".repeat(depth + 1);
         println!("{indent}repo: {}", git_url);
@@ -997,11 +3436,18 @@ fn install_plugin_recursive(
             println!("{indent}source path: {}", subpath);
         }
     }
-    let source_path = if let Some(p) = subpath {
+    let source_path = if let Some(p) = &subpath {
         repo_root.join(p)
     } else {
         repo_root.clone()
     };
+    let commit = clone_cache.commit_of(&repo_root);
+    let repo = RepoContext {
+        root: &repo_root,
+        url: &git_url,
+        git_ref: git_ref.as_deref(),
+        commit: commit.as_deref(),
+    };
 
     if source_path.exists() {
         let mut skill_paths = discover_skill_dirs(&source_path, plugin)?;
@@ -1010,16 +3456,20 @@ fn install_plugin_recursive(
                 skill_paths = filter_skill_paths(skill_paths, selected);
             }
             if skill_paths.is_empty() {
-                return Ok(Vec::new());
+                return Ok(PluginInstall::default());
             }
             if options.dry_run {
                 if !options.quiet {
                     let indent = "  ".repeat(depth + 1);
                     println!("{indent}skills detected: {}", format_skill_names(&skill_paths));
                 }
-                return Ok(extract_skill_names(skill_paths));
+                return Ok(PluginInstall {
+                    descriptions: skill_descriptions(&skill_paths),
+                    skills: extract_skill_names(skill_paths),
+                    origin: None,
+                });
             }
-            return install_skills_from_paths(skills_dir, skill_paths, options);
+            return install_skills_with_origin(skills_dir, repo, subpath, skill_paths, options);
         }
     }
 
@@ -1043,8 +3493,7 @@ fn install_plugin_recursive(
             return install_from_marketplace_entry(
                 nested_plugin,
                 skills_dir,
-                &repo_root,
-                &git_url,
+                repo,
                 nested_root,
                 depth + 1,
                 visited,
@@ -1074,22 +3523,22 @@ fn install_plugin_recursive(
     )
 }
 
+#[allow(clippy::too_many_arguments)]
 fn install_from_marketplace_entry(
     plugin: &model::PluginEntry,
     skills_dir: &Path,
-    repo_root: &Path,
-    repo_url: &str,
+    repo: RepoContext,
     plugin_root: Option<&str>,
     depth: usize,
     visited: &mut HashSet<String>,
     options: InstallOptions,
     selected_skills: Option<&HashSet<String>>,
     clone_cache: &mut CloneCache,
-) -> Result<Vec<String>> {
+) -> Result<PluginInstall> {
     match &plugin.source {
         PluginSource::Path(path) => {
             let resolved_path = apply_plugin_root(path, plugin_root);
-            let source_path = repo_root.join(resolved_path);
+            let source_path = repo.root.join(&resolved_path);
             if !source_path.exists() {
                 return handle_missing_skills(
                     options,
@@ -1116,9 +3565,13 @@ fn install_from_marketplace_entry(
                     println!("{indent}marketplace entry: path");
                     println!("{indent}skills detected: {}", format_skill_names(&skill_paths));
                 }
-                return Ok(extract_skill_names(skill_paths));
+                return Ok(PluginInstall {
+                    descriptions: skill_descriptions(&skill_paths),
+                    skills: extract_skill_names(skill_paths),
+                    origin: None,
+                });
             }
-            install_skills_from_paths(skills_dir, skill_paths, options)
+            install_skills_with_origin(skills_dir, repo, Some(resolved_path), skill_paths, options)
         }
         PluginSource::Object(_) => {
             if options.dry_run && !options.quiet {
@@ -1128,7 +3581,7 @@ fn install_from_marketplace_entry(
             install_plugin_recursive(
                 plugin,
                 skills_dir,
-                repo_url,
+                repo.url,
                 plugin_root,
                 depth,
                 visited,
@@ -1140,12 +3593,12 @@ fn install_from_marketplace_entry(
     }
 }
 
-fn handle_missing_skills(options: InstallOptions, message: &str) -> Result<Vec<String>> {
+fn handle_missing_skills(options: InstallOptions, message: &str) -> Result<PluginInstall> {
     if options.dry_run {
         if !options.quiet {
             println!("  {}", message);
         }
-        return Ok(Vec::new());
+        return Ok(PluginInstall::default());
     }
     Err(anyhow!(message.to_string()))
 }
@@ -1185,6 +3638,54 @@ fn filter_skill_paths(
         .collect()
 }
 
+fn install_skills_with_origin(
+    skills_dir: &Path,
+    repo: RepoContext,
+    subpath: Option<String>,
+    skill_paths: Vec<PathBuf>,
+    options: InstallOptions,
+) -> Result<PluginInstall> {
+    let Some(commit) = repo.commit else {
+        info!("{} has no commit to pin; its skills are not recorded in the lockfile.", repo.url);
+        return Ok(PluginInstall {
+            skills: install_skills_from_paths(skills_dir, skill_paths, options)?,
+            ..PluginInstall::default()
+        });
+    };
+    let skill_paths_by_name = skill_paths
+        .iter()
+        .filter_map(|path| {
+            let name = path.file_name()?.to_str()?.to_string();
+            Some((name, relative_repo_path(repo.root, path)))
+        })
+        .collect();
+    let skills = install_skills_from_paths(skills_dir, skill_paths, options)?;
+    Ok(PluginInstall {
+        skills,
+        origin: Some(InstallOrigin {
+            url: repo.url.to_string(),
+            subpath,
+            git_ref: repo.git_ref.map(|value| value.to_string()),
+            commit: commit.to_string(),
+            skill_paths: skill_paths_by_name,
+        }),
+        ..PluginInstall::default()
+    })
+}
+
+fn relative_repo_path(repo_root: &Path, path: &Path) -> String {
+    let relative = path.strip_prefix(repo_root).unwrap_or(path);
+    let parts: Vec<String> = relative
+        .components()
+        .map(|component| component.as_os_str().to_string_lossy().to_string())
+        .collect();
+    if parts.is_empty() {
+        ".".to_string()
+    } else {
+        parts.join("/")
+    }
+}
+
 fn install_skills_from_paths(
     skills_dir: &Path,
     skill_paths: Vec<PathBuf>,
@@ -1203,6 +3704,16 @@ fn install_skills_from_paths(
         if !options.quiet {
             println!("Installing skill: {}", skill_name);
         }
+        match skill::read(&skill_path) {
+            Ok(Some(skill::Frontmatter {
+                name: Some(name), ..
+            })) if name != skill_name => warn!(
+                "SKILL.md names the skill {:?}, but it is installed from and as {:?}",
+                name, skill_name
+            ),
+            Ok(_) => {}
+            Err(err) => warn!("{:#}", err),
+        }
         let dest = skills_dir.join(&skill_name);
         if dest.exists() {
             fs::remove_dir_all(&dest).with_context(|| {
@@ -1216,26 +3727,40 @@ fn install_skills_from_paths(
     Ok(installed_skills)
 }
 
+fn skill_descriptions(skill_paths: &[PathBuf]) -> HashMap<String, String> {
+    skill_paths
+        .iter()
+        .filter_map(|path| {
+            let name = path.file_name()?.to_str()?.to_string();
+            Some((name, skill::description(path)?))
+        })
+        .collect()
+}
+
 fn read_marketplace_from_repo(repo_root: &Path) -> Option<Marketplace> {
+    load_marketplace_file(repo_root).ok()
+}
+
+fn load_marketplace_file(repo_root: &Path) -> Result<Marketplace> {
     let path = repo_root.join(".claude-plugin/marketplace.json");
-    let content = fs::read_to_string(path).ok()?;
-    serde_json::from_str(&content).ok()
+    let content = fs::read_to_string(&path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    serde_json::from_str(&content).with_context(|| format!("Failed to parse {}", path.display()))
 }
 
 fn resolve_plugin_url(
     plugin: &model::PluginEntry,
     marketplace_repo: &str,
     plugin_root: Option<&str>,
-) -> (String, Option<String>, Option<String>) {
+) -> PluginLocation {
+    let (marketplace_repo, marketplace_ref) = util::split_marketplace_ref(marketplace_repo);
     let base_repo_url = resolve_marketplace_repo_url(marketplace_repo);
     let get_override_url = |plugin: &model::PluginEntry| -> Option<String> {
-        if let Some(author) = &plugin.author {
-            if let Some(url) = &author.url {
-                if url.starts_with("http") || url.starts_with("git@") {
-                    return Some(url.clone());
-                } else {
-                    return Some(format!("https://github.com/{}.git", url));
-                }
+        if let Some(url) = plugin.author.as_ref().and_then(|author| author.url.as_ref()) {
+            if url.starts_with("http") || url.starts_with("git@") {
+                return Some(url.clone());
+            } else {
+                return Some(format!("https://github.com/{}.git", url));
             }
         }
         if let Some(repo) = &plugin.repository {
@@ -1250,34 +3775,54 @@ fn resolve_plugin_url(
 
     match &plugin.source {
         PluginSource::Path(p) => {
-            let repo_url = get_override_url(plugin).unwrap_or_else(|| base_repo_url.clone());
             let resolved_path = apply_plugin_root(p, plugin_root);
-
-            (repo_url, Some(resolved_path), None)
+            match get_override_url(plugin) {
+                Some(url) => PluginLocation {
+                    url,
+                    subpath: Some(resolved_path),
+                    git_ref: None,
+                    sha: None,
+                },
+                // Plugins living in the marketplace repo follow its pinned ref.
+                None => PluginLocation {
+                    url: base_repo_url,
+                    subpath: Some(resolved_path),
+                    git_ref: marketplace_ref.map(|value| value.to_string()),
+                    sha: None,
+                },
+            }
         }
         PluginSource::Object(def) => match def {
-            SourceDefinition::Github {
-                repo,
-                ref_,
-                sha: _,
-            } => {
+            SourceDefinition::Github { repo, ref_, sha } => {
                 // For explicit Github source, use the defined repo, ignoring overrides
-                (format!("https://github.com/{}.git", repo), None, ref_.clone())
+                PluginLocation {
+                    url: format!("https://github.com/{}.git", repo),
+                    subpath: None,
+                    git_ref: ref_.clone(),
+                    sha: sha.clone(),
+                }
             }
-            SourceDefinition::Url {
-                url,
-                ref_,
-                sha: _,
-            } => {
+            SourceDefinition::Url { url, ref_, sha } => {
                 // For explicit URL source, use the defined URL, ignoring overrides
-                (url.clone(), None, ref_.clone())
+                PluginLocation {
+                    url: url.clone(),
+                    subpath: None,
+                    git_ref: ref_.clone(),
+                    sha: sha.clone(),
+                }
             }
+            SourceDefinition::Npm { package, version } => PluginLocation {
+                url: npm::location_url(package),
+                subpath: None,
+                git_ref: version.clone(),
+                sha: None,
+            },
         },
     }
 }
 
 fn resolve_marketplace_repo_url(marketplace_repo: &str) -> String {
-    if marketplace_repo.starts_with("http") || marketplace_repo.starts_with("git@") {
+    if util::is_git_url(marketplace_repo) || util::local_marketplace_path(marketplace_repo).is_some() {
         marketplace_repo.to_string()
     } else {
         format!("https://github.com/{}.git", marketplace_repo)
@@ -1307,6 +3852,24 @@ fn discover_skill_dirs(
     plugin_root: &Path,
     plugin: &model::PluginEntry,
 ) -> Result<Vec<PathBuf>> {
+    let mut seen = HashSet::new();
+    let mut unique = Vec::new();
+    for path in find_skill_dirs(plugin_root, plugin)? {
+        let Some(name) = path.file_name().and_then(|name| name.to_str()) else {
+            continue;
+        };
+        if seen.insert(name.to_string()) {
+            unique.push(path);
+        } else {
+            warn!("Duplicate skill name {}, skipping {:?}", name, path);
+        }
+    }
+
+    Ok(unique)
+}
+
+/// Every skill directory of a plugin, including ones sharing a name.
+fn find_skill_dirs(plugin_root: &Path, plugin: &model::PluginEntry) -> Result<Vec<PathBuf>> {
     let mut skill_paths = Vec::new();
     if let Some(paths) = extract_skill_paths(plugin) {
         skill_paths.extend(collect_skills_from_candidates(plugin_root, &paths)?);
@@ -1330,20 +3893,7 @@ fn discover_skill_dirs(
         skill_paths.push(plugin_root.to_path_buf());
     }
 
-    let mut seen = HashSet::new();
-    let mut unique = Vec::new();
-    for path in skill_paths {
-        let Some(name) = path.file_name().and_then(|name| name.to_str()) else {
-            continue;
-        };
-        if seen.insert(name.to_string()) {
-            unique.push(path);
-        } else {
-            warn!("Duplicate skill name {}, skipping {:?}", name, path);
-        }
-    }
-
-    Ok(unique)
+    Ok(skill_paths)
 }
 
 fn extract_skill_paths(plugin: &model::PluginEntry) -> Option<Vec<String>> {
@@ -1356,6 +3906,28 @@ fn extract_skill_paths(plugin: &model::PluginEntry) -> Option<Vec<String>> {
     None
 }
 
+/// Skill names declared in marketplace.json, provided every declared path names
+/// one skill (`skills/lint` or `skills/lint/SKILL.md`) rather than a folder of them.
+fn declared_skill_names(plugin: &model::PluginEntry) -> Option<Vec<String>> {
+    let mut names = Vec::new();
+    for path in extract_skill_paths(plugin)? {
+        let path = Path::new(&path);
+        let dir = if path.file_name().and_then(|name| name.to_str()) == Some("SKILL.md") {
+            path.parent()?
+        } else {
+            path
+        };
+        let name = dir.file_name()?.to_str()?;
+        if matches!(name, "skills" | "agents") {
+            return None;
+        }
+        if !names.iter().any(|existing| existing == name) {
+            names.push(name.to_string());
+        }
+    }
+    Some(names)
+}
+
 fn extract_paths_from_value(value: &Value) -> Option<Vec<String>> {
     match value {
         Value::String(path) => Some(vec![path.to_string()]),
@@ -1397,10 +3969,9 @@ fn collect_skills_from_candidates(
                 .file_name()
                 .and_then(|name| name.to_str())
                 == Some("SKILL.md")
+                && let Some(parent) = candidate_path.parent()
             {
-                if let Some(parent) = candidate_path.parent() {
-                    skill_paths.push(parent.to_path_buf());
-                }
+                skill_paths.push(parent.to_path_buf());
             }
             continue;
         }
@@ -1425,10 +3996,9 @@ fn collect_skills_from_candidates(
                 .file_name()
                 .and_then(|name| name.to_str())
                 == Some("SKILL.md")
+                && let Some(parent) = path.parent()
             {
-                if let Some(parent) = path.parent() {
-                    skill_paths.push(parent.to_path_buf());
-                }
+                skill_paths.push(parent.to_path_buf());
             }
         }
     }
@@ -1465,6 +4035,19 @@ mod tests {
     use serde_json::json;
     use std::collections::HashMap;
 
+    /// Quiet, single-job options; tests override what they exercise.
+    fn install_options() -> InstallOptions {
+        InstallOptions {
+            dry_run: false,
+            max_depth: 1,
+            quiet: true,
+            scope: Scope::Project,
+            fetch: FetchMode::Auto,
+            jobs: 1,
+            offline: false,
+        }
+    }
+
     fn create_dummy_plugin(
         source: PluginSource,
         author_url: Option<String>,
@@ -1489,17 +4072,126 @@ mod tests {
     fn test_resolve_path_defaults_to_marketplace() {
         let source = PluginSource::Path("./skills/test".to_string());
         let plugin = create_dummy_plugin(source, None, None);
-        let (url, subpath, _) = resolve_plugin_url(&plugin, "owner/marketplace", None);
+        let PluginLocation { url, subpath, .. } = resolve_plugin_url(&plugin, "owner/marketplace", None);
+
+        assert_eq!(url, "https://github.com/owner/marketplace.git");
+        assert_eq!(subpath, Some("./skills/test".to_string()));
+    }
+
+    #[test]
+    fn test_resolve_path_uses_marketplace_ref() {
+        let source = PluginSource::Path("./skills/test".to_string());
+        let plugin = create_dummy_plugin(source, None, None);
+        let PluginLocation { url, subpath, git_ref, .. } =
+            resolve_plugin_url(&plugin, "owner/marketplace@v1.2.0", None);
 
         assert_eq!(url, "https://github.com/owner/marketplace.git");
         assert_eq!(subpath, Some("./skills/test".to_string()));
+        assert_eq!(git_ref, Some("v1.2.0".to_string()));
+    }
+
+    #[test]
+    fn test_plan_local_marketplace_without_cloning() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path();
+        fs::create_dir_all(root.join(".claude-plugin")).unwrap();
+        fs::write(
+            root.join(".claude-plugin/marketplace.json"),
+            r#"{
+                "name": "local",
+                "owner": { "name": "Dev" },
+                "plugins": [{ "name": "tools", "source": "./plugins/tools" }]
+            }"#,
+        )
+        .unwrap();
+        for skill in ["lint", "review"] {
+            let skill_dir = root.join("plugins/tools/skills").join(skill);
+            fs::create_dir_all(&skill_dir).unwrap();
+            let frontmatter = format!("---\nname: {}\ndescription: Runs {}.\n---\n", skill, skill);
+            fs::write(skill_dir.join("SKILL.md"), frontmatter).unwrap();
+        }
+
+        let spec = format!("file://{}", root.display());
+        let options = InstallOptions {
+            dry_run: true,
+            ..install_options()
+        };
+        let cache_dir = tempfile::tempdir().unwrap();
+        let mut clone_cache = temp_clone_cache(&cache_dir);
+        let marketplace = fetch_marketplace(&spec, &mut clone_cache, options).unwrap();
+        let plan =
+            plan_marketplace_skills(&marketplace, &spec, PlanMode::Declared, options, &mut clone_cache)
+                .unwrap();
+
+        assert_eq!(plan.all_skills, ["lint", "review"]);
+        assert_eq!(plan.descriptions["review"], "Runs review.");
+        assert!(clone_cache.entries.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_plan_uses_declared_skills_without_fetching() {
+        let marketplace: Marketplace = serde_json::from_value(json!({
+            "name": "team",
+            "owner": { "name": "Team" },
+            "plugins": [
+                {
+                    "name": "remote",
+                    "source": { "source": "url", "url": "https://example.invalid/remote.git" },
+                    "skills": ["./skills/lint", "skills/review/SKILL.md"]
+                },
+                {
+                    "name": "bundle",
+                    "source": { "source": "url", "url": "https://example.invalid/bundle.git" },
+                    "skills": "./skills/"
+                }
+            ]
+        }))
+        .unwrap();
+        let remote = &marketplace.plugins[0];
+        assert_eq!(declared_skill_names(remote).unwrap(), ["lint", "review"]);
+        // A folder of skills says nothing about their names.
+        assert!(declared_skill_names(&marketplace.plugins[1]).is_none());
+
+        let options = InstallOptions {
+            dry_run: true,
+            fetch: FetchMode::Git,
+            ..install_options()
+        };
+        let cache_dir = tempfile::tempdir().unwrap();
+        let mut clone_cache = temp_clone_cache(&cache_dir);
+        let declared_only = Marketplace {
+            plugins: marketplace.plugins.into_iter().take(1).collect(),
+            ..marketplace
+        };
+        let plan = plan_marketplace_skills(
+            &declared_only,
+            "team/skills",
+            PlanMode::Declared,
+            options,
+            &mut clone_cache,
+        )
+        .unwrap();
+        assert_eq!(plan.all_skills, ["lint", "review"]);
+        assert!(clone_cache.entries.lock().unwrap().is_empty());
+
+        assert!(
+            plan_marketplace_skills(&declared_only, "team/skills", PlanMode::Scan, options, &mut clone_cache)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_parse_symref_head() {
+        let output = "ref: refs/heads/master\tHEAD\n0123456789abcdef0123456789abcdef01234567\tHEAD";
+        assert_eq!(parse_symref_head(output), Some("master".to_string()));
+        assert_eq!(parse_symref_head("0123456789abcdef\tHEAD"), None);
     }
 
     #[test]
     fn test_resolve_path_applies_plugin_root() {
         let source = PluginSource::Path("formatter".to_string());
         let plugin = create_dummy_plugin(source, None, None);
-        let (url, subpath, _) =
+        let PluginLocation { url, subpath, .. } =
             resolve_plugin_url(&plugin, "owner/marketplace", Some("./plugins"));
 
         assert_eq!(url, "https://github.com/owner/marketplace.git");
@@ -1510,7 +4202,7 @@ mod tests {
     fn test_resolve_path_does_not_double_prefix() {
         let source = PluginSource::Path("./plugins/formatter".to_string());
         let plugin = create_dummy_plugin(source, None, None);
-        let (_, subpath, _) = resolve_plugin_url(&plugin, "owner/marketplace", Some("./plugins"));
+        let PluginLocation { subpath, .. } = resolve_plugin_url(&plugin, "owner/marketplace", Some("./plugins"));
 
         assert_eq!(subpath, Some("./plugins/formatter".to_string()));
     }
@@ -1519,7 +4211,7 @@ mod tests {
     fn test_resolve_path_with_marketplace_url() {
         let source = PluginSource::Path("./skills/test".to_string());
         let plugin = create_dummy_plugin(source, None, None);
-        let (url, subpath, _) = resolve_plugin_url(
+        let PluginLocation { url, subpath, .. } = resolve_plugin_url(
             &plugin,
             "https://github.com/example/repo.git",
             None,
@@ -1533,7 +4225,7 @@ mod tests {
     fn test_resolve_path_uses_author_url_override() {
         let source = PluginSource::Path("./skills/test".to_string());
         let plugin = create_dummy_plugin(source, Some("other/repo".to_string()), None);
-        let (url, subpath, _) = resolve_plugin_url(&plugin, "owner/marketplace", None);
+        let PluginLocation { url, subpath, .. } = resolve_plugin_url(&plugin, "owner/marketplace", None);
 
         assert_eq!(url, "https://github.com/other/repo.git");
         assert_eq!(subpath, Some("./skills/test".to_string()));
@@ -1547,7 +4239,7 @@ mod tests {
             None,
             Some("https://github.com/repo/over".to_string()),
         );
-        let (url, subpath, _) = resolve_plugin_url(&plugin, "owner/marketplace", None);
+        let PluginLocation { url, subpath, .. } = resolve_plugin_url(&plugin, "owner/marketplace", None);
 
         assert_eq!(url, "https://github.com/repo/over");
         assert_eq!(subpath, Some("./skills/test".to_string()));
@@ -1561,11 +4253,429 @@ mod tests {
             sha: None,
         });
         let plugin = create_dummy_plugin(source, Some("override/repo".to_string()), None);
-        let (url, _, _) = resolve_plugin_url(&plugin, "owner/marketplace", None);
+        let PluginLocation { url, .. } = resolve_plugin_url(&plugin, "owner/marketplace", None);
 
         assert_eq!(url, "https://github.com/original/repo.git");
     }
 
+    #[test]
+    fn test_resolve_url_object_keeps_sha() {
+        let source = PluginSource::Object(SourceDefinition::Url {
+            url: "https://gitlab.com/team/plugin.git".to_string(),
+            ref_: Some("main".to_string()),
+            sha: Some("0123456789abcdef0123456789abcdef01234567".to_string()),
+        });
+        let plugin = create_dummy_plugin(source, None, None);
+        let location = resolve_plugin_url(&plugin, "owner/marketplace", None);
+
+        assert_eq!(location.git_ref.as_deref(), Some("main"));
+        assert_eq!(
+            location.sha.as_deref(),
+            Some("0123456789abcdef0123456789abcdef01234567")
+        );
+    }
+
+    #[test]
+    fn test_is_commit_sha() {
+        assert!(is_commit_sha("0123456789abcdef0123456789abcdef01234567"));
+        assert!(!is_commit_sha("0123456"));
+        assert!(!is_commit_sha("v1.2.0"));
+        assert!(!is_commit_sha("release-0123456789abcdef0123456789abcdef0"));
+    }
+
+    #[test]
+    fn test_checkout_pinned_sha() {
+        let temp = tempfile::tempdir().unwrap();
+        let repo = temp.path();
+        let git = |args: &[&str]| run_git(repo, args).unwrap();
+        git(&["init", "--quiet"]);
+        git(&["config", "user.email", "dev@example.com"]);
+        git(&["config", "user.name", "Dev"]);
+        fs::write(repo.join("SKILL.md"), "first").unwrap();
+        git(&["add", "."]);
+        git(&["commit", "--quiet", "-m", "first"]);
+        let first = git(&["rev-parse", "HEAD"]);
+        fs::write(repo.join("SKILL.md"), "second").unwrap();
+        git(&["commit", "--quiet", "-am", "second"]);
+
+        let options = install_options();
+        let mut location = PluginLocation {
+            url: format!("file://{}", repo.display()),
+            subpath: None,
+            git_ref: None,
+            sha: Some(first),
+        };
+        let cache_dir = tempfile::tempdir().unwrap();
+        let clone_cache = temp_clone_cache(&cache_dir);
+        let root = clone_cache.checkout(&location, options).unwrap();
+        assert_eq!(fs::read_to_string(root.join("SKILL.md")).unwrap(), "first");
+
+        // A full commit given as the ref takes the same path.
+        location.git_ref = location.sha.take();
+        let root = clone_cache.checkout(&location, options).unwrap();
+        assert_eq!(fs::read_to_string(root.join("SKILL.md")).unwrap(), "first");
+
+        location.git_ref = None;
+        location.sha = Some("0000000000000000000000000000000000000000".to_string());
+        let err = clone_cache.checkout(&location, options).unwrap_err();
+        assert!(format!("{:#}", err).contains("not found"));
+    }
+
+    fn temp_clone_cache(dir: &tempfile::TempDir) -> CloneCache {
+        CloneCache::with_store(cache::Store::at(dir.path().to_path_buf()).unwrap())
+    }
+
+    #[test]
+    fn test_plan_prefetches_sources_and_reports_every_failure() {
+        let temp = tempfile::tempdir().unwrap();
+        for name in ["alpha", "beta"] {
+            let repo = temp.path().join(name);
+            let skill_dir = repo.join("skills").join(name);
+            fs::create_dir_all(&skill_dir).unwrap();
+            fs::write(skill_dir.join("SKILL.md"), name).unwrap();
+            let git = |args: &[&str]| run_git(&repo, args).unwrap();
+            git(&["init", "--quiet", "--initial-branch", "main"]);
+            git(&["add", "."]);
+            git(&["config", "user.email", "dev@example.com"]);
+            git(&["config", "user.name", "Dev"]);
+            git(&["commit", "--quiet", "-m", "init"]);
+        }
+        let plugin = |name: &str, repo: &str| {
+            json!({
+                "name": name,
+                "source": {
+                    "source": "url",
+                    "url": format!("file://{}", temp.path().join(repo).display()),
+                    "ref": "main"
+                }
+            })
+        };
+        let marketplace: Marketplace = serde_json::from_value(json!({
+            "name": "team",
+            "owner": { "name": "Team" },
+            "plugins": [
+                plugin("alpha", "alpha"),
+                plugin("beta", "beta"),
+                plugin("alpha-again", "alpha"),
+                plugin("gone", "missing"),
+                plugin("also-gone", "missing-too"),
+            ]
+        }))
+        .unwrap();
+        let options = InstallOptions {
+            dry_run: true,
+            jobs: 4,
+            ..install_options()
+        };
+        let cache_dir = tempfile::tempdir().unwrap();
+        let mut clone_cache = temp_clone_cache(&cache_dir);
+
+        let err = plan_marketplace_skills(
+            &marketplace,
+            "team/skills",
+            PlanMode::Declared,
+            options,
+            &mut clone_cache,
+        )
+            .err()
+            .expect("missing repositories should fail planning")
+            .to_string();
+        assert!(err.contains("Failed to resolve 2 plugin(s)"), "{}", err);
+        assert!(err.contains("\n  gone: ") && err.contains("\n  also-gone: "), "{}", err);
+        // Both working repositories were cloned once, however many plugins use them.
+        assert_eq!(clone_cache.store.list().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_search_plan_keeps_what_resolves() {
+        let temp = tempfile::tempdir().unwrap();
+        let repo = temp.path().join("alpha");
+        let skill_dir = repo.join("skills").join("review");
+        fs::create_dir_all(&skill_dir).unwrap();
+        fs::write(skill_dir.join("SKILL.md"), "---\nname: review\ndescription: Reviews diffs.\n---\n").unwrap();
+        let git = |args: &[&str]| run_git(&repo, args).unwrap();
+        git(&["init", "--quiet", "--initial-branch", "main"]);
+        git(&["add", "."]);
+        git(&["config", "user.email", "dev@example.com"]);
+        git(&["config", "user.name", "Dev"]);
+        git(&["commit", "--quiet", "-m", "init"]);
+        let source = |name: &str| json!({ "source": "url", "url": format!("file://{}", temp.path().join(name).display()) });
+        // A pinned commit has to be fetched, which fails for a missing repository.
+        let missing = json!({
+            "source": "url",
+            "url": format!("file://{}", temp.path().join("missing").display()),
+            "sha": "0".repeat(40)
+        });
+        let marketplace: Marketplace = serde_json::from_value(json!({
+            "name": "team",
+            "owner": { "name": "Team" },
+            "plugins": [
+                { "name": "alpha", "source": source("alpha") },
+                { "name": "declared", "source": missing, "skills": ["./skills/deploy"] },
+                { "name": "gone", "source": missing },
+                { "name": "empty", "source": source("empty"), "skills": ["./skills/notes"] },
+            ]
+        }))
+        .unwrap();
+        let options = InstallOptions {
+            jobs: 2,
+            ..install_options()
+        };
+        let cache_dir = tempfile::tempdir().unwrap();
+        let mut clone_cache = temp_clone_cache(&cache_dir);
+
+        let plan = search_plan(&marketplace, "team/skills", true, options, &mut clone_cache);
+        assert_eq!(plan.by_plugin["alpha"], ["review"]);
+        assert_eq!(plan.descriptions["review"], "Reviews diffs.");
+        assert_eq!(plan.by_plugin["declared"], ["deploy"]);
+        assert_eq!(plan.by_plugin["empty"], ["notes"]);
+        assert!(plan.by_plugin.get("gone").is_none_or(|skills| skills.is_empty()));
+    }
+
+    #[test]
+    fn test_offline_reports_what_is_not_cached() {
+        let options = InstallOptions {
+            dry_run: true,
+            jobs: 2,
+            offline: true,
+            ..install_options()
+        };
+        let cache_dir = tempfile::tempdir().unwrap();
+        let mut clone_cache = temp_clone_cache(&cache_dir);
+        let err = fetch_marketplace("team/skills", &mut clone_cache, options).unwrap_err();
+        assert!(err.to_string().contains("team/skills is not in the cache"), "{}", err);
+
+        let marketplace = json!({
+            "name": "team",
+            "owner": { "name": "Team" },
+            "plugins": [
+                {
+                    "name": "reviewer",
+                    "source": { "source": "github", "repo": "team/reviewer", "ref": "v1" }
+                },
+                {
+                    "name": "deployer",
+                    "source": { "source": "url", "url": "https://example.invalid/deployer.git" }
+                }
+            ]
+        });
+        clone_cache
+            .store
+            .save_marketplace("team/skills", &marketplace.to_string())
+            .unwrap();
+        let marketplace = fetch_marketplace("team/skills", &mut clone_cache, options).unwrap();
+        assert_eq!(marketplace.plugins.len(), 2);
+
+        let err = plan_marketplace_skills(
+            &marketplace,
+            "team/skills",
+            PlanMode::Declared,
+            options,
+            &mut clone_cache,
+        )
+        .err()
+        .expect("nothing is cached")
+        .to_string();
+        assert!(
+            err.contains("reviewer: https://github.com/team/reviewer.git at v1 is not in the cache"),
+            "{}",
+            err
+        );
+        assert!(
+            err.contains("deployer: https://example.invalid/deployer.git is not in the cache"),
+            "{}",
+            err
+        );
+        assert!(err.ends_with("Run without --offline once to fetch them."));
+    }
+
+    #[test]
+    fn test_archive_sources_do_not_mix_commits_or_take_pins() {
+        let options = InstallOptions {
+            dry_run: true,
+            fetch: FetchMode::Tarball,
+            offline: true,
+            ..install_options()
+        };
+        let cache_dir = tempfile::tempdir().unwrap();
+        let clone_cache = temp_clone_cache(&cache_dir);
+        let url = "https://github.com/team/skills.git";
+        let (older, newer) = ("1".repeat(40), "2".repeat(40));
+        for (commit, subpath, fetched_at) in [(&older, "alpha", 1), (&newer, "beta", 2)] {
+            let key = CacheKey {
+                repo: url.to_string(),
+                git_ref: None,
+                commit: Some(commit.clone()),
+                subpath: Some(subpath.to_string()),
+            };
+            let staging = clone_cache.store.stage().unwrap();
+            fs::create_dir_all(staging.checkout_dir().join(subpath)).unwrap();
+            let mut meta = cache::EntryMeta::new(&key, cache::EntryKind::Tarball);
+            meta.commit = Some(commit.clone());
+            meta.fetched_at = fetched_at;
+            clone_cache.store.insert(&key, staging, meta).unwrap();
+        }
+        let location = |subpath: &str| PluginLocation {
+            url: url.to_string(),
+            subpath: Some(subpath.to_string()),
+            git_ref: None,
+            sha: None,
+        };
+
+        // Every subpath comes from the newest download, not from whichever has it.
+        let root = clone_cache.checkout(&location("beta"), options).unwrap();
+        assert_eq!(clone_cache.commit_of(&root), Some(newer.clone()));
+        let err = clone_cache.checkout(&location("alpha"), options).unwrap_err();
+        assert!(err.to_string().contains(&format!("at {} is not in the cache", newer)), "{}", err);
+
+        let pinned = PluginLocation {
+            url: "https://example.com/skills.tar.gz".to_string(),
+            subpath: None,
+            git_ref: None,
+            sha: Some(older),
+        };
+        let err = clone_cache.checkout(&pinned, options).unwrap_err();
+        assert!(err.to_string().contains("plain archive and cannot be pinned"), "{}", err);
+    }
+
+    /// Answers HTTP requests on `listener` with `routes` (path to body) for as long as the test runs.
+    fn serve(listener: std::net::TcpListener, routes: HashMap<String, Vec<u8>>) {
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let mut stream = stream.unwrap();
+                let mut request = String::new();
+                io::BufRead::read_line(&mut io::BufReader::new(&stream), &mut request).unwrap();
+                let path = request.split_whitespace().nth(1).unwrap_or_default();
+                let (status, body) = match routes.get(path) {
+                    Some(body) => ("200 OK", body.as_slice()),
+                    None => ("404 Not Found", &[][..]),
+                };
+                let head = format!(
+                    "HTTP/1.1 {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                    status,
+                    body.len()
+                );
+                let _ = stream.write_all(head.as_bytes()).and_then(|_| stream.write_all(body));
+            }
+        });
+    }
+
+    fn npm_tarball(content: &str) -> Vec<u8> {
+        let mut builder = tar::Builder::new(flate2::write::GzEncoder::new(
+            Vec::new(),
+            flate2::Compression::default(),
+        ));
+        let mut header = tar::Header::new_gnu();
+        header.set_size(content.len() as u64);
+        header.set_mode(0o644);
+        header.set_cksum();
+        builder
+            .append_data(&mut header, "package/skills/notes/SKILL.md", content.as_bytes())
+            .unwrap();
+        builder.into_inner().unwrap().finish().unwrap()
+    }
+
+    #[test]
+    fn test_npm_package_is_verified_and_cached_for_offline_use() {
+        use base64::Engine;
+        use sha2::Digest;
+
+        let tarballs: Vec<(&str, Vec<u8>)> = ["1.0.0", "1.1.0", "2.0.0"]
+            .into_iter()
+            .map(|version| (version, npm_tarball(version)))
+            .collect();
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let registry = format!("http://{}", listener.local_addr().unwrap());
+        let mut routes = HashMap::new();
+        let mut versions = serde_json::Map::new();
+        for (version, tarball) in &tarballs {
+            let path = format!("/skills/-/skills-{}.tgz", version);
+            // 2.0.0 is served with content that does not match its integrity.
+            let published: &[u8] = if *version == "2.0.0" { b"tampered" } else { tarball };
+            let digest = sha2::Sha512::digest(published);
+            versions.insert(
+                version.to_string(),
+                json!({ "dist": {
+                    "tarball": format!("{}{}", registry, path),
+                    "integrity": format!("sha512-{}", base64::engine::general_purpose::STANDARD.encode(digest)),
+                } }),
+            );
+            routes.insert(path, tarball.clone());
+        }
+        let packument = json!({ "dist-tags": { "latest": "1.1.0" }, "versions": versions });
+        routes.insert("/skills".to_string(), packument.to_string().into_bytes());
+        serve(listener, routes);
+
+        let mut options = install_options();
+        let cache_dir = tempfile::tempdir().unwrap();
+        let mut clone_cache = temp_clone_cache(&cache_dir);
+        clone_cache.npm_registry = registry;
+        let location = |version: Option<&str>| PluginLocation {
+            url: npm::location_url("skills"),
+            subpath: None,
+            git_ref: version.map(|value| value.to_string()),
+            sha: None,
+        };
+        let skill = |root: PathBuf| fs::read_to_string(root.join("skills/notes/SKILL.md")).unwrap();
+
+        assert_eq!(skill(clone_cache.checkout(&location(None), options).unwrap()), "1.1.0");
+        assert_eq!(skill(clone_cache.checkout(&location(Some("1.0.0")), options).unwrap()), "1.0.0");
+        let err = clone_cache.checkout(&location(Some("2")), options).unwrap_err();
+        assert!(format!("{:#}", err).contains("does not match its integrity"), "{:#}", err);
+        let err = clone_cache.checkout(&location(Some("1.2.0")), options).unwrap_err();
+        assert!(err.to_string().contains("No version of skills matches 1.2.0"), "{}", err);
+
+        // Offline, a later run picks among the versions already in the cache.
+        options.offline = true;
+        let clone_cache = temp_clone_cache(&cache_dir);
+        let root = clone_cache.checkout(&location(Some(">=1 <2")), options).unwrap();
+        assert_eq!(skill(root.clone()), "1.1.0");
+        assert_eq!(clone_cache.commit_of(&root).as_deref(), Some("1.1.0"));
+        assert_eq!(skill(clone_cache.checkout(&location(Some("~1.0")), options).unwrap()), "1.0.0");
+        let err = clone_cache.checkout(&location(Some("^2")), options).unwrap_err();
+        assert!(err.to_string().contains("npm:skills at ^2 is not in the cache"), "{}", err);
+    }
+
+    #[test]
+    fn test_cached_branch_is_refreshed_across_runs() {
+        let temp = tempfile::tempdir().unwrap();
+        let repo = temp.path();
+        let git = |args: &[&str]| run_git(repo, args).unwrap();
+        git(&["init", "--quiet", "--initial-branch", "main"]);
+        git(&["config", "user.email", "dev@example.com"]);
+        git(&["config", "user.name", "Dev"]);
+        fs::write(repo.join("SKILL.md"), "first").unwrap();
+        git(&["add", "."]);
+        git(&["commit", "--quiet", "-m", "first"]);
+
+        let options = install_options();
+        let location = PluginLocation {
+            url: format!("file://{}", repo.display()),
+            subpath: None,
+            git_ref: Some("main".to_string()),
+            sha: None,
+        };
+        let cache_dir = tempfile::tempdir().unwrap();
+        let root = temp_clone_cache(&cache_dir).checkout(&location, options).unwrap();
+        assert_eq!(fs::read_to_string(root.join("SKILL.md")).unwrap(), "first");
+
+        fs::write(repo.join("SKILL.md"), "second").unwrap();
+        git(&["commit", "--quiet", "-am", "second"]);
+        // Offline runs take the cached copy as is.
+        let offline = InstallOptions {
+            offline: true,
+            ..options
+        };
+        let root = temp_clone_cache(&cache_dir).checkout(&location, offline).unwrap();
+        assert_eq!(fs::read_to_string(root.join("SKILL.md")).unwrap(), "first");
+
+        let clone_cache = temp_clone_cache(&cache_dir);
+        let root = clone_cache.checkout(&location, options).unwrap();
+        assert_eq!(fs::read_to_string(root.join("SKILL.md")).unwrap(), "second");
+        assert_eq!(clone_cache.commit_of(&root), Some(git(&["rev-parse", "HEAD"])));
+        assert_eq!(clone_cache.store.list().unwrap().len(), 1);
+    }
+
     #[test]
     fn test_resolve_github_object_defaults_to_source_repo() {
         let source = PluginSource::Object(SourceDefinition::Github {
@@ -1574,7 +4684,7 @@ mod tests {
             sha: None,
         });
         let plugin = create_dummy_plugin(source, None, None);
-        let (url, _, _) = resolve_plugin_url(&plugin, "owner/marketplace", None);
+        let PluginLocation { url, .. } = resolve_plugin_url(&plugin, "owner/marketplace", None);
 
         assert_eq!(url, "https://github.com/original/repo.git");
     }
@@ -1637,4 +4747,218 @@ mod tests {
         assert_eq!(skills.len(), 1);
         assert_eq!(skills[0].file_name().unwrap(), "moonbit-agent-guide");
     }
+
+    #[test]
+    fn test_installed_copy_matches_source_hash() {
+        let temp = tempfile::tempdir().unwrap();
+        let repo_root = temp.path().join("repo");
+        let skill_dir = repo_root.join("skills/review");
+        fs::create_dir_all(skill_dir.join(".git")).unwrap();
+        fs::write(skill_dir.join("SKILL.md"), "skill").unwrap();
+        fs::write(skill_dir.join(".git/HEAD"), "ref").unwrap();
+        let dest = temp.path().join("installed/review");
+        copy_dir_all(&skill_dir, &dest).unwrap();
+
+        assert_eq!(
+            lockfile::hash_dir(&dest).unwrap(),
+            lockfile::hash_dir(&skill_dir).unwrap()
+        );
+        assert_eq!(relative_repo_path(&repo_root, &repo_root.join("./skills/review")), "skills/review");
+        assert_eq!(relative_repo_path(&repo_root, &repo_root), ".");
+    }
+
+    #[test]
+    fn test_plugin_metadata_without_marketplace() {
+        let metadata: PluginInstallMetadata =
+            serde_json::from_str(r#"{"version":"1.0.0","skills":["lint"]}"#).unwrap();
+        assert_eq!(metadata.marketplace, None);
+
+        let temp = tempfile::tempdir().unwrap();
+        let updated = PluginInstallMetadata {
+            marketplace: Some("owner/marketplace".to_string()),
+            ..metadata
+        };
+        write_plugin_metadata(temp.path(), "tools", &updated).unwrap();
+        let plugins = read_all_plugin_metadata(temp.path());
+        assert_eq!(plugins.len(), 1);
+        assert_eq!(plugins[0].0, "tools");
+        assert_eq!(plugins[0].1.marketplace.as_deref(), Some("owner/marketplace"));
+    }
+
+    #[test]
+    fn test_info_reports_skill_state_and_source() {
+        let temp = tempfile::tempdir().unwrap();
+        let skill_dir = temp.path().join("lint");
+        fs::create_dir_all(skill_dir.join("scripts")).unwrap();
+        fs::write(skill_dir.join("SKILL.md"), "---\nname: lint\n---\n").unwrap();
+        fs::write(skill_dir.join("scripts/run.sh"), "echo lint\n").unwrap();
+        let locked = LockedPlugin {
+            name: "tools".to_string(),
+            target: "claude".to_string(),
+            marketplace: "owner/marketplace".to_string(),
+            version: Some("1.0.0".to_string()),
+            url: "https://github.com/owner/tools.git".to_string(),
+            subpath: None,
+            git_ref: Some("main".to_string()),
+            commit: "abc123".to_string(),
+            skills: vec![LockedSkill {
+                name: "lint".to_string(),
+                path: "skills/lint".to_string(),
+                hash: lockfile::hash_dir(&skill_dir).unwrap(),
+            }],
+        };
+        let lockfile = Lockfile {
+            plugins: vec![locked],
+            ..Lockfile::default()
+        };
+        let entry = InstalledPlugin {
+            name: "tools".to_string(),
+            target: targets::registry().parse("claude").unwrap(),
+            scope: Scope::Project,
+            metadata: serde_json::from_str(r#"{"version":"1.0.0","skills":["lint"]}"#).unwrap(),
+        };
+
+        // Installs from before the metadata recorded a source fall back to skop.lock.
+        let (source, hashes) = install_record(&entry, Some(&lockfile));
+        assert_eq!(source.unwrap().commit, "abc123");
+        assert_eq!(format_contents(&skill_dir), "2 file(s), 29 B");
+        assert_eq!(skill_state(&skill_dir, hashes.get("lint")), "unmodified");
+        assert_eq!(skill_state(&skill_dir, None), "unknown");
+
+        fs::write(skill_dir.join("scripts/run.sh"), "echo edited\n").unwrap();
+        assert_eq!(skill_state(&skill_dir, hashes.get("lint")), "modified");
+        assert_eq!(skill_state(&temp.path().join("gone"), None), "missing");
+
+        let global = InstalledPlugin {
+            scope: Scope::Global,
+            ..entry
+        };
+        let (source, hashes) = install_record(&global, Some(&lockfile));
+        assert!(source.is_none() && hashes.is_empty());
+    }
+
+    #[test]
+    fn test_compare_plugin_version() {
+        let mut plugin = create_dummy_plugin(PluginSource::Path(".".to_string()), None, None);
+        plugin.version = Some("1.2.0".to_string());
+        let installed = |version: Option<&str>| PluginInstallMetadata {
+            version: version.map(|value| value.to_string()),
+            skills: vec!["lint".to_string()],
+            marketplace: None,
+            source: None,
+            hashes: BTreeMap::new(),
+        };
+
+        assert_eq!(compare_plugin_version(&plugin, None), VersionStatus::NotInstalled);
+        assert_eq!(
+            compare_plugin_version(&plugin, Some(&installed(Some("1.1.9")))),
+            VersionStatus::Outdated
+        );
+        assert_eq!(
+            compare_plugin_version(&plugin, Some(&installed(Some("1.2.0")))),
+            VersionStatus::UpToDate
+        );
+        assert_eq!(
+            compare_plugin_version(&plugin, Some(&installed(Some("latest")))),
+            VersionStatus::Unparsable
+        );
+        assert_eq!(
+            compare_plugin_version(&plugin, Some(&installed(None))),
+            VersionStatus::MissingInstalledVersion
+        );
+        plugin.version = None;
+        assert_eq!(
+            compare_plugin_version(&plugin, Some(&installed(Some("1.2.0")))),
+            VersionStatus::MissingMarketplaceVersion
+        );
+    }
+
+    fn create_dummy_plan(skills: &[&str]) -> SkillPlan {
+        SkillPlan {
+            by_plugin: HashMap::new(),
+            all_skills: skills.iter().map(|skill| skill.to_string()).collect(),
+            descriptions: HashMap::new(),
+        }
+    }
+
+    #[test]
+    fn test_resolve_requested_skills_defaults_to_all() {
+        let plan = create_dummy_plan(&["lint", "review"]);
+        let selected = resolve_requested_skills(&plan, &[]).unwrap();
+
+        assert_eq!(selected.len(), 2);
+        assert!(selected.contains("lint"));
+        assert!(selected.contains("review"));
+    }
+
+    #[test]
+    fn test_resolve_requested_skills_rejects_unknown_names() {
+        let plan = create_dummy_plan(&["lint", "review"]);
+        let err = resolve_requested_skills(&plan, &["lint".to_string(), "deploy".to_string()])
+            .unwrap_err()
+            .to_string();
+
+        assert!(err.contains("deploy"));
+        assert!(err.contains("Available skills: lint, review"));
+    }
+
+    #[test]
+    fn test_select_skills_by_name_matches_every_target() {
+        let codex = targets::registry().parse("codex").unwrap();
+        let entries = vec![
+            SkillEntry {
+                name: "lint".to_string(),
+                path: PathBuf::from(".codex/skills/lint"),
+                target: codex.clone(),
+                scope: Scope::Project,
+            },
+            SkillEntry {
+                name: "lint".to_string(),
+                path: PathBuf::from("~/.codex/skills/lint"),
+                target: codex.clone(),
+                scope: Scope::Global,
+            },
+            SkillEntry {
+                name: "review".to_string(),
+                path: PathBuf::from(".codex/skills/review"),
+                target: codex.clone(),
+                scope: Scope::Project,
+            },
+        ];
+        let selected = select_skills_by_name(&entries, &["lint".to_string()], &[]).unwrap();
+        assert_eq!(selected.len(), 2);
+        assert!(selected.iter().all(|entry| entry.name == "lint"));
+        assert_eq!(selected[0].location(), "codex");
+        assert_eq!(selected[1].location(), "codex, global");
+
+        let err = select_skills_by_name(&entries, &["deploy".to_string()], &[])
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("deploy"));
+    }
+
+    #[test]
+    fn test_resolve_spec_skills_limits_to_plugins() {
+        let mut plan = create_dummy_plan(&["format", "lint", "review"]);
+        plan.by_plugin
+            .insert("tools".to_string(), vec!["format".to_string(), "lint".to_string()]);
+        plan.by_plugin
+            .insert("reviewer".to_string(), vec!["review".to_string()]);
+        let mut spec = MarketplaceSpec {
+            repo: "owner/marketplace".to_string(),
+            plugins: vec!["tools".to_string()],
+            ..Default::default()
+        };
+
+        let selected = resolve_spec_skills(&plan, &spec).unwrap();
+        assert_eq!(selected.len(), 2);
+        assert!(!selected.contains("review"));
+
+        spec.skills = vec!["review".to_string()];
+        assert!(resolve_spec_skills(&plan, &spec).is_err());
+
+        spec.plugins = vec!["missing".to_string()];
+        let err = resolve_spec_skills(&plan, &spec).unwrap_err().to_string();
+        assert!(err.contains("Available plugins: reviewer, tools"));
+    }
 }
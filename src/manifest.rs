@@ -0,0 +1,131 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+pub const MANIFEST_NAME: &str = "skop.toml";
+
+/// Project-level declaration of the skills a repository needs.
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Manifest {
+    /// Default targets for marketplaces that do not list their own.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub targets: Vec<String>,
+    #[serde(default, rename = "marketplace")]
+    pub marketplaces: Vec<MarketplaceSpec>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MarketplaceSpec {
+    pub repo: String,
+    /// Plugins to install from; empty means every plugin.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub plugins: Vec<String>,
+    /// Skills to install; empty means every skill of the selected plugins.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub skills: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub targets: Vec<String>,
+}
+
+impl Manifest {
+    pub fn load(path: &Path) -> Result<Option<Self>> {
+        if !path.exists() {
+            return Ok(None);
+        }
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        let manifest = toml::from_str(&content)
+            .with_context(|| format!("Failed to parse {}", path.display()))?;
+        Ok(Some(manifest))
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let content = toml::to_string_pretty(self)?;
+        fs::write(path, content).with_context(|| format!("Failed to write {}", path.display()))
+    }
+
+    pub fn targets_for<'a>(&'a self, spec: &'a MarketplaceSpec) -> &'a [String] {
+        if spec.targets.is_empty() {
+            &self.targets
+        } else {
+            &spec.targets
+        }
+    }
+
+    /// Replaces the entry for `spec.repo`, keeping its position if it already exists.
+    pub fn upsert(&mut self, spec: MarketplaceSpec) {
+        if let Some(existing) = self
+            .marketplaces
+            .iter_mut()
+            .find(|existing| existing.repo == spec.repo)
+        {
+            *existing = spec;
+        } else {
+            self.marketplaces.push(spec);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_manifest() {
+        let manifest: Manifest = toml::from_str(
+            r#"
+targets = ["codex"]
+
+[[marketplace]]
+repo = "my-org/coding-skills"
+skills = ["lint"]
+
+[[marketplace]]
+repo = "my-org/other"
+plugins = ["tools"]
+targets = ["opencode", "antigravity"]
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(manifest.marketplaces.len(), 2);
+        assert_eq!(manifest.targets_for(&manifest.marketplaces[0]), ["codex"]);
+        assert_eq!(
+            manifest.targets_for(&manifest.marketplaces[1]),
+            ["opencode", "antigravity"]
+        );
+        assert_eq!(manifest.marketplaces[1].plugins, ["tools"]);
+    }
+
+    #[test]
+    fn test_rejects_unknown_fields() {
+        let result: Result<Manifest, _> = toml::from_str(
+            r#"
+[[marketplace]]
+repo = "my-org/coding-skills"
+skill = ["lint"]
+"#,
+        );
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_upsert_replaces_existing_repo() {
+        let mut manifest = Manifest::default();
+        manifest.upsert(MarketplaceSpec {
+            repo: "my-org/coding-skills".to_string(),
+            ..Default::default()
+        });
+        manifest.upsert(MarketplaceSpec {
+            repo: "my-org/coding-skills".to_string(),
+            skills: vec!["lint".to_string()],
+            ..Default::default()
+        });
+
+        assert_eq!(manifest.marketplaces.len(), 1);
+        assert_eq!(manifest.marketplaces[0].skills, ["lint"]);
+    }
+}
@@ -0,0 +1,189 @@
+use crate::config::{self, Config};
+use crate::util;
+use anyhow::{anyhow, Result};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::PathBuf;
+use std::sync::OnceLock;
+
+static REGISTRY: OnceLock<BTreeMap<String, String>> = OnceLock::new();
+
+/// Sets the registered marketplaces from the merged config. Call once at startup.
+pub fn init_registry(config: &Config) {
+    let _ = REGISTRY.set(config.marketplaces.clone());
+}
+
+/// Registered marketplaces by name; a project entry named like a user entry is
+/// ignored, with a warning when loading the config.
+pub fn registry() -> &'static BTreeMap<String, String> {
+    REGISTRY.get_or_init(BTreeMap::new)
+}
+
+/// The marketplace `spec` refers to: a registered name, optionally pinned with
+/// `@ref`, or otherwise `spec` itself. Local directories become absolute paths.
+pub fn resolve(spec: &str) -> Result<String> {
+    util::absolute_local_spec(&resolve_in(registry(), spec)?)
+}
+
+fn resolve_in(registry: &BTreeMap<String, String>, spec: &str) -> Result<String> {
+    if let Some(repo) = registry.get(spec) {
+        return Ok(repo.clone());
+    }
+    if let Some((name, git_ref)) = spec.split_once('@')
+        && let Some(repo) = registry.get(name)
+    {
+        if util::local_marketplace_path(repo).is_some() {
+            return Err(anyhow!(
+                "{} is the local marketplace {}, which cannot be pinned to {}",
+                name,
+                repo,
+                git_ref
+            ));
+        }
+        if util::split_marketplace_ref(repo).1.is_some() {
+            return Err(anyhow!(
+                "{} is registered as {}, which already pins a ref",
+                name,
+                repo
+            ));
+        }
+        return Ok(util::join_marketplace_ref(repo, git_ref));
+    }
+    Ok(spec.to_string())
+}
+
+/// The config file a marketplace is registered in.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Origin {
+    User,
+    Project,
+}
+
+impl Origin {
+    pub fn path(self) -> Result<PathBuf> {
+        match self {
+            Origin::User => config::user_config_path()
+                .ok_or_else(|| anyhow!("Could not determine the user config directory")),
+            Origin::Project => Ok(config::project_config_path()),
+        }
+    }
+}
+
+impl fmt::Display for Origin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Origin::User => f.pad("user"),
+            Origin::Project => f.pad("project"),
+        }
+    }
+}
+
+/// Every registration, user config first, with the file it is in.
+pub fn list() -> Result<Vec<(Origin, String, String)>> {
+    let mut entries = Vec::new();
+    for origin in [Origin::User, Origin::Project] {
+        let Ok(path) = origin.path() else { continue };
+        let Some(config) = Config::load(&path)? else { continue };
+        for (name, repo) in config.marketplaces {
+            entries.push((origin, name, repo));
+        }
+    }
+    Ok(entries)
+}
+
+/// Registers `repo` as `name`; `false` if it already is.
+pub fn register(origin: Origin, name: &str, repo: &str) -> Result<bool> {
+    check_name(name)?;
+    let path = origin.path()?;
+    let config = Config::load(&path)?.unwrap_or_default();
+    match config.marketplaces.get(name) {
+        Some(existing) if existing == repo => return Ok(false),
+        Some(existing) => {
+            return Err(anyhow!(
+                "A marketplace named {} is already registered for {} in {}; remove it first",
+                name,
+                existing,
+                path.display()
+            ));
+        }
+        None => {}
+    }
+    if origin == Origin::Project
+        && let Ok(user_path) = Origin::User.path()
+        && let Some(user) = Config::load(&user_path)?
+        && let Some(existing) = user.marketplaces.get(name)
+        && existing != repo
+    {
+        return Err(anyhow!(
+            "{} is registered for {} in {}, which takes precedence; pick another name",
+            name,
+            existing,
+            user_path.display()
+        ));
+    }
+    config::set_marketplace(&path, name, Some(repo))?;
+    Ok(true)
+}
+
+/// Unregisters `name` and returns the marketplace it stood for.
+pub fn unregister(origin: Origin, name: &str) -> Result<String> {
+    let path = origin.path()?;
+    let config = Config::load(&path)?.unwrap_or_default();
+    let repo = config
+        .marketplaces
+        .get(name)
+        .cloned()
+        .ok_or_else(|| anyhow!("No marketplace named {} in {}", name, path.display()))?;
+    config::set_marketplace(&path, name, None)?;
+    Ok(repo)
+}
+
+/// Names must not read as a marketplace spec themselves.
+pub fn check_name(name: &str) -> Result<()> {
+    let valid = !name.is_empty()
+        && !name.starts_with('.')
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'));
+    if !valid {
+        return Err(anyhow!(
+            "Invalid marketplace name {:?}: use letters, digits, '-', '_' and '.'",
+            name
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve_registered_names() {
+        let registry = BTreeMap::from([
+            ("team".to_string(), "my-org/skills".to_string()),
+            ("pinned".to_string(), "my-org/pinned@v1".to_string()),
+            (
+                "gitlab".to_string(),
+                "https://gitlab.com/my-org/skills.git".to_string(),
+            ),
+            ("local".to_string(), "/srv/skills".to_string()),
+        ]);
+
+        let resolve = |spec: &str| resolve_in(&registry, spec).unwrap();
+        assert_eq!(resolve("team"), "my-org/skills");
+        assert_eq!(resolve("team@v2"), "my-org/skills@v2");
+        assert_eq!(resolve("gitlab@release"), "https://gitlab.com/my-org/skills.git#release");
+        assert_eq!(resolve("local"), "/srv/skills");
+        assert_eq!(resolve("other/repo@v1"), "other/repo@v1");
+        assert_eq!(resolve("./team"), "./team");
+        // A registered ref is not overridden, and local directories have none.
+        assert!(resolve_in(&registry, "pinned@v2").is_err());
+        assert!(resolve_in(&registry, "local@v2").is_err());
+
+        assert!(check_name("team-skills_2").is_ok());
+        assert!(check_name("my-org/skills").is_err());
+        assert!(check_name("team@v1").is_err());
+        assert!(check_name(".").is_err());
+    }
+}
@@ -59,13 +59,111 @@ pub enum PluginSource {
 pub enum SourceDefinition {
     Github {
         repo: String,
-        ref_: Option<String>, // "ref" is a reserved keyword in Rust (kinda, but safe as field identifier usually, but let's use ref_)
+        // `ref` is a Rust keyword; `rename_all` above only renames the variants.
+        #[serde(rename = "ref")]
+        ref_: Option<String>,
         sha: Option<String>,
     },
     Url {
         url: String,
+        #[serde(rename = "ref")]
         ref_: Option<String>,
         sha: Option<String>,
     },
-    // The spec also mentions "npm" but says it's not fully implemented.
+    Npm {
+        package: String,
+        /// Exact version, dist-tag, or semver range; the `latest` tag if omitted.
+        version: Option<String>,
+    },
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MARKETPLACE_JSON: &str = r#"{
+  "name": "team-skills",
+  "owner": { "name": "Team", "email": "team@example.com" },
+  "metadata": { "description": "Shared skills", "version": "2.0.0", "pluginRoot": "./plugins" },
+  "plugins": [
+    {
+      "name": "formatter",
+      "source": "formatter",
+      "version": "1.0.0",
+      "keywords": ["format", "style"],
+      "strict": false
+    },
+    {
+      "name": "reviewer",
+      "source": { "source": "github", "repo": "team/reviewer", "ref": "v1.2" },
+      "description": "Code review helpers",
+      "author": { "name": "Dev", "url": "https://example.com" }
+    },
+    {
+      "name": "deployer",
+      "source": {
+        "source": "url",
+        "url": "https://gitlab.com/team/deployer.git",
+        "ref": "release",
+        "sha": "0123456789abcdef0123456789abcdef01234567"
+      },
+      "version": "0.3.0"
+    },
+    {
+      "name": "publisher",
+      "source": { "source": "npm", "package": "@team/publisher", "version": "^2.1.0" }
+    }
+  ]
+}"#;
+
+    fn source(marketplace: &Marketplace, index: usize) -> &SourceDefinition {
+        match &marketplace.plugins[index].source {
+            PluginSource::Object(def) => def,
+            PluginSource::Path(path) => panic!("expected a source object, got path {}", path),
+        }
+    }
+
+    #[test]
+    fn test_parse_marketplace_sources() {
+        let marketplace: Marketplace = serde_json::from_str(MARKETPLACE_JSON).unwrap();
+
+        assert_eq!(
+            marketplace.metadata.as_ref().unwrap().plugin_root.as_deref(),
+            Some("./plugins")
+        );
+        assert!(matches!(&marketplace.plugins[0].source, PluginSource::Path(path) if path == "formatter"));
+        assert_eq!(marketplace.plugins[0].extra["keywords"][0], "format");
+        assert!(matches!(
+            source(&marketplace, 1),
+            SourceDefinition::Github { repo, ref_: Some(r), sha: None } if repo == "team/reviewer" && r == "v1.2"
+        ));
+        assert!(matches!(
+            source(&marketplace, 2),
+            SourceDefinition::Url { ref_: Some(r), sha: Some(sha), .. }
+                if r == "release" && sha == "0123456789abcdef0123456789abcdef01234567"
+        ));
+        assert!(matches!(
+            source(&marketplace, 3),
+            SourceDefinition::Npm { package, version: Some(v) } if package == "@team/publisher" && v == "^2.1.0"
+        ));
+    }
+
+    #[test]
+    fn test_marketplace_round_trip() {
+        let marketplace: Marketplace = serde_json::from_str(MARKETPLACE_JSON).unwrap();
+        let serialized = serde_json::to_value(&marketplace).unwrap();
+
+        let github = &serialized["plugins"][1]["source"];
+        assert_eq!(github["source"], "github");
+        assert_eq!(github["ref"], "v1.2");
+        assert!(github.get("ref_").is_none());
+        assert_eq!(serialized["plugins"][0]["strict"], false);
+
+        let reparsed: Marketplace = serde_json::from_value(serialized).unwrap();
+        assert!(matches!(
+            source(&reparsed, 2),
+            SourceDefinition::Url { ref_: Some(r), .. } if r == "release"
+        ));
+        assert_eq!(reparsed.plugins.len(), 4);
+    }
 }
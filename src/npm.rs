@@ -0,0 +1,284 @@
+use crate::config::Config;
+use crate::tarball;
+use anyhow::{anyhow, Context, Result};
+use base64::Engine;
+use serde::Deserialize;
+use sha2::{Digest, Sha512};
+use std::collections::HashMap;
+use std::io::{self, Seek};
+use std::path::Path;
+use std::sync::OnceLock;
+
+/// Prefix of the location URL given to npm sources, e.g. `npm:@scope/name`.
+pub const URL_PREFIX: &str = "npm:";
+pub const DEFAULT_REGISTRY: &str = "https://registry.npmjs.org";
+
+static REGISTRY: OnceLock<String> = OnceLock::new();
+
+/// Sets the registry from `[npm] registry` in the user config. Call once at startup.
+pub fn init_registry(config: &Config) {
+    if let Some(registry) = &config.npm.registry {
+        let _ = REGISTRY.set(registry.trim_end_matches('/').to_string());
+    }
+}
+
+pub fn registry() -> &'static str {
+    REGISTRY.get_or_init(|| DEFAULT_REGISTRY.to_string())
+}
+
+pub fn location_url(package: &str) -> String {
+    format!("{}{}", URL_PREFIX, package)
+}
+
+pub fn package_of(url: &str) -> Option<&str> {
+    url.strip_prefix(URL_PREFIX)
+}
+
+/// A published version and where to download it.
+pub struct Release {
+    pub version: String,
+    pub tarball: String,
+    /// Subresource Integrity string of the tarball, e.g. `sha512-<base64>`.
+    pub integrity: Option<String>,
+}
+
+/// The parts of a registry package document skop needs.
+#[derive(Deserialize)]
+struct Packument {
+    #[serde(rename = "dist-tags", default)]
+    dist_tags: HashMap<String, String>,
+    #[serde(default)]
+    versions: HashMap<String, PackageVersion>,
+}
+
+#[derive(Deserialize)]
+struct PackageVersion {
+    dist: Dist,
+}
+
+#[derive(Deserialize)]
+struct Dist {
+    tarball: String,
+    #[serde(default)]
+    integrity: Option<String>,
+}
+
+/// Resolves `spec` (an exact version, a dist-tag, or a semver range; the
+/// `latest` tag if `None`) against `registry`.
+pub fn resolve(registry: &str, package: &str, spec: Option<&str>) -> Result<Release> {
+    let url = packument_url(registry, package);
+    let resp = reqwest::blocking::Client::new()
+        .get(&url)
+        // The abbreviated document is much smaller and has everything used here.
+        .header("Accept", "application/vnd.npm.install-v1+json; q=1.0, application/json; q=0.8")
+        .send()
+        .with_context(|| format!("Failed to query {}", url))?;
+    if !resp.status().is_success() {
+        return Err(anyhow!(
+            "Failed to query {}: status {}",
+            url,
+            resp.status()
+        ));
+    }
+    let packument: Packument = resp
+        .json()
+        .with_context(|| format!("Failed to parse the registry response for {}", package))?;
+    let version = select_version(
+        packument.versions.keys().map(|version| version.as_str()),
+        &packument.dist_tags,
+        spec,
+    )?
+    .ok_or_else(|| {
+        anyhow!(
+            "No version of {} matches {}",
+            package,
+            spec.unwrap_or("latest")
+        )
+    })?;
+    let dist = &packument.versions[&version].dist;
+    Ok(Release {
+        version,
+        tarball: dist.tarball.clone(),
+        integrity: dist.integrity.clone(),
+    })
+}
+
+/// Downloads `release` and unpacks it into `dest`, once its sha512 matches the
+/// integrity the registry published.
+pub fn download(release: &Release, dest: &Path) -> Result<tarball::Extracted> {
+    let expected = release
+        .integrity
+        .as_deref()
+        .and_then(sha512_digest)
+        .ok_or_else(|| anyhow!("The registry lists no sha512 integrity for {}", release.tarball))?;
+    let mut archive = tempfile::tempfile()?;
+    tarball::save(&release.tarball, &mut archive)?;
+    archive.rewind()?;
+    let mut hasher = Sha512::new();
+    io::copy(&mut archive, &mut hasher)?;
+    if hasher.finalize().as_slice() != expected.as_slice() {
+        return Err(anyhow!(
+            "{} does not match its integrity {}",
+            release.tarball,
+            release.integrity.as_deref().unwrap_or_default()
+        ));
+    }
+    archive.rewind()?;
+    tarball::extract(archive, dest, tarball::Layout::Plain)
+        .with_context(|| format!("Failed to unpack {}", release.tarball))
+}
+
+/// The sha512 digest in an integrity string, which may list several hashes.
+fn sha512_digest(integrity: &str) -> Option<Vec<u8>> {
+    integrity
+        .split_whitespace()
+        .filter_map(|hash| hash.strip_prefix("sha512-"))
+        .find_map(|digest| base64::engine::general_purpose::STANDARD.decode(digest).ok())
+}
+
+/// Picks the version `spec` refers to among `versions`: an exact version, a
+/// dist-tag, or an npm range, of which the highest match wins. Without dist-tags
+/// (e.g. offline), `latest` means the highest release.
+pub fn select_version<'a>(
+    versions: impl Iterator<Item = &'a str>,
+    dist_tags: &HashMap<String, String>,
+    spec: Option<&str>,
+) -> Result<Option<String>> {
+    let versions: Vec<&str> = versions.collect();
+    let spec = spec.unwrap_or("latest").trim();
+    if versions.contains(&spec) {
+        return Ok(Some(spec.to_string()));
+    }
+    if let Some(tagged) = dist_tags.get(spec) {
+        return Ok(versions.contains(&tagged.as_str()).then(|| tagged.clone()));
+    }
+    let range = if spec == "latest" {
+        vec![semver::VersionReq::STAR]
+    } else {
+        parse_range(spec).ok_or_else(|| {
+            anyhow!(
+                "{} is not a version, dist-tag, or version range skop understands",
+                spec
+            )
+        })?
+    };
+    Ok(versions
+        .into_iter()
+        .filter_map(|version| semver::Version::parse(version).ok())
+        .filter(|version| range.iter().any(|req| req.matches(version)))
+        .max()
+        .map(|version| version.to_string()))
+}
+
+const OPERATOR_CHARS: &str = "<>=~^";
+
+/// An npm range as alternatives (`a || b`) of semver requirements. npm separates
+/// comparators with spaces, allows hyphen ranges (`1.2 - 2`), and reads a bare
+/// version as exactly that version, where semver would read a caret range.
+fn parse_range(spec: &str) -> Option<Vec<semver::VersionReq>> {
+    spec.split("||")
+        .map(|alternative| {
+            let alternative = alternative.trim();
+            let comparators = match alternative.split_once(" - ") {
+                Some((low, high)) => vec![format!(">={}", low.trim()), format!("<={}", high.trim())],
+                None => {
+                    let mut comparators = Vec::new();
+                    let mut pending = String::new();
+                    for token in alternative.split_whitespace() {
+                        pending.push_str(token);
+                        // An operator written apart from its version, as in `>= 1.2`.
+                        if !token.chars().all(|c| OPERATOR_CHARS.contains(c)) {
+                            comparators.push(std::mem::take(&mut pending));
+                        }
+                    }
+                    if !pending.is_empty() {
+                        return None;
+                    }
+                    comparators
+                }
+            };
+            let comparators: Vec<String> = comparators
+                .iter()
+                .map(|comparator| {
+                    let split = comparator
+                        .find(|c| !OPERATOR_CHARS.contains(c))
+                        .unwrap_or(comparator.len());
+                    let (operator, version) = comparator.split_at(split);
+                    let version = version.strip_prefix('v').unwrap_or(version);
+                    let wildcard = matches!(version, "*" | "x" | "X");
+                    let operator = if operator.is_empty() && !wildcard { "=" } else { operator };
+                    format!("{}{}", operator, version)
+                })
+                .collect();
+            if comparators.is_empty() {
+                return Some(semver::VersionReq::STAR);
+            }
+            semver::VersionReq::parse(&comparators.join(", ")).ok()
+        })
+        .collect()
+}
+
+/// Scoped names keep their `@` but escape the slash: `@scope%2fname`.
+fn packument_url(registry: &str, package: &str) -> String {
+    format!(
+        "{}/{}",
+        registry.trim_end_matches('/'),
+        package.replace('/', "%2f")
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_select_version() {
+        let versions = ["1.0.0", "1.2.0", "1.3.0-beta.1", "2.0.0"];
+        let tags = HashMap::from([
+            ("latest".to_string(), "1.2.0".to_string()),
+            ("next".to_string(), "1.3.0-beta.1".to_string()),
+        ]);
+        let select = |spec: Option<&str>, tags: &HashMap<String, String>| {
+            select_version(versions.iter().copied(), tags, spec).unwrap()
+        };
+
+        assert_eq!(select(None, &tags).as_deref(), Some("1.2.0"));
+        assert_eq!(select(Some("next"), &tags).as_deref(), Some("1.3.0-beta.1"));
+        assert_eq!(select(Some("1.0.0"), &tags).as_deref(), Some("1.0.0"));
+        assert_eq!(select(Some("^1.0"), &tags).as_deref(), Some("1.2.0"));
+        assert_eq!(select(Some("^3"), &tags), None);
+        // Offline there are no tags; `latest` is the highest release.
+        assert_eq!(select(None, &HashMap::new()).as_deref(), Some("2.0.0"));
+
+        // npm reads a bare version as exact, not as ^1.1.0.
+        assert_eq!(select(Some("1.1.0"), &tags), None);
+        assert_eq!(select(Some("v1.0.0"), &tags).as_deref(), Some("1.0.0"));
+        assert_eq!(select(Some("1.x"), &tags).as_deref(), Some("1.2.0"));
+        assert_eq!(select(Some("1"), &tags).as_deref(), Some("1.2.0"));
+        assert_eq!(select(Some(">=1 <2"), &tags).as_deref(), Some("1.2.0"));
+        assert_eq!(select(Some(">= 1.0.0 < 1.2.0"), &tags).as_deref(), Some("1.0.0"));
+        assert_eq!(select(Some("1.0.0 - 1.1"), &tags).as_deref(), Some("1.0.0"));
+        assert_eq!(select(Some("^3 || ~1.0"), &tags).as_deref(), Some("1.0.0"));
+        assert_eq!(select(Some("*"), &tags).as_deref(), Some("2.0.0"));
+        assert!(select_version(versions.iter().copied(), &tags, Some("beta")).is_err());
+    }
+
+    #[test]
+    fn test_sha512_digest() {
+        let digest = sha512_digest("sha1-AAAA sha512-AAECAw==").unwrap();
+        assert_eq!(digest, vec![0, 1, 2, 3]);
+        assert_eq!(sha512_digest("sha1-AAAA"), None);
+    }
+
+    #[test]
+    fn test_packument_url() {
+        assert_eq!(
+            packument_url("https://registry.npmjs.org/", "@team/skills"),
+            "https://registry.npmjs.org/@team%2fskills"
+        );
+        assert_eq!(
+            packument_url("http://localhost:4873", "skills"),
+            "http://localhost:4873/skills"
+        );
+    }
+}
@@ -0,0 +1,140 @@
+use crate::model::{Marketplace, PluginEntry};
+use crate::SkillPlan;
+
+/// Fields of `PluginEntry.extra` searched besides the name and description.
+const TAG_FIELDS: [&str; 3] = ["keywords", "tags", "category"];
+
+/// A plugin, or one of its skills, that matches a search.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Hit {
+    pub plugin: String,
+    /// The matching skill; `None` when the plugin itself matched.
+    pub skill: Option<String>,
+    pub description: Option<String>,
+    /// Skills `skop add` would install for the hit.
+    pub skills: Vec<String>,
+}
+
+/// Case-insensitively matches every word of `query` against each plugin's name,
+/// description, and keywords, then against the names and descriptions of the
+/// skills of plugins that did not match themselves.
+pub fn search(marketplace: &Marketplace, plan: &SkillPlan, query: &str) -> Vec<Hit> {
+    let words: Vec<String> = query.split_whitespace().map(str::to_lowercase).collect();
+    let matches = |text: &str| {
+        let text = text.to_lowercase();
+        words.iter().all(|word| text.contains(word))
+    };
+
+    let mut hits = Vec::new();
+    for plugin in &marketplace.plugins {
+        let mut skills = plan.by_plugin.get(&plugin.name).cloned().unwrap_or_default();
+        skills.sort();
+        if matches(&plugin_text(plugin)) {
+            hits.push(Hit {
+                plugin: plugin.name.clone(),
+                skill: None,
+                description: plugin.description.clone(),
+                skills,
+            });
+            continue;
+        }
+        for skill in &skills {
+            let description = plan.descriptions.get(skill);
+            let text = match description {
+                Some(description) => format!("{}\n{}", skill, description),
+                None => skill.clone(),
+            };
+            if matches(&text) {
+                hits.push(Hit {
+                    plugin: plugin.name.clone(),
+                    skill: Some(skill.clone()),
+                    description: description.cloned(),
+                    skills: vec![skill.clone()],
+                });
+            }
+        }
+    }
+    hits
+}
+
+fn plugin_text(plugin: &PluginEntry) -> String {
+    let mut parts = vec![plugin.name.clone()];
+    parts.extend(plugin.description.clone());
+    for field in TAG_FIELDS {
+        match plugin.extra.get(field) {
+            Some(serde_json::Value::String(value)) => parts.push(value.clone()),
+            Some(serde_json::Value::Array(values)) => parts.extend(
+                values
+                    .iter()
+                    .filter_map(|value| value.as_str())
+                    .map(|value| value.to_string()),
+            ),
+            _ => {}
+        }
+    }
+    parts.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_search_plugins_and_skills() {
+        let marketplace: Marketplace = serde_json::from_str(
+            r#"{
+  "name": "team",
+  "owner": { "name": "Team" },
+  "plugins": [
+    { "name": "infra", "source": "./infra", "description": "Cloud tooling", "keywords": ["Terraform", "aws"] },
+    { "name": "tools", "source": "./tools", "description": "Everyday helpers", "category": "productivity" },
+    { "name": "docs", "source": "./docs" }
+  ]
+}"#,
+        )
+        .unwrap();
+        let plan = SkillPlan {
+            by_plugin: HashMap::from([
+                ("infra".to_string(), vec!["plan".to_string(), "apply".to_string()]),
+                ("tools".to_string(), vec!["lint".to_string(), "tf-fmt".to_string()]),
+                ("docs".to_string(), vec!["readme".to_string()]),
+            ]),
+            all_skills: Vec::new(),
+            descriptions: HashMap::from([(
+                "tf-fmt".to_string(),
+                "Formats Terraform files.".to_string(),
+            )]),
+        };
+
+        let hits = search(&marketplace, &plan, "terraform");
+        assert_eq!(
+            hits,
+            [
+                Hit {
+                    plugin: "infra".to_string(),
+                    skill: None,
+                    description: Some("Cloud tooling".to_string()),
+                    skills: vec!["apply".to_string(), "plan".to_string()],
+                },
+                Hit {
+                    plugin: "tools".to_string(),
+                    skill: Some("tf-fmt".to_string()),
+                    description: Some("Formats Terraform files.".to_string()),
+                    skills: vec!["tf-fmt".to_string()],
+                },
+            ]
+        );
+
+        let plugins = |query: &str| {
+            search(&marketplace, &plan, query)
+                .into_iter()
+                .map(|hit| hit.skill.unwrap_or(hit.plugin))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(plugins("PRODUCTIVITY"), ["tools"]);
+        assert_eq!(plugins("readme"), ["readme"]);
+        assert_eq!(plugins("cloud aws"), ["infra"]);
+        assert!(plugins("cloud gcp").is_empty());
+    }
+}
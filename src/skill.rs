@@ -0,0 +1,162 @@
+use anyhow::{anyhow, Context, Result};
+use serde::{Deserialize, Deserializer, Serialize};
+use serde_norway::Value;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+pub const SKILL_FILE: &str = "SKILL.md";
+
+/// The YAML frontmatter at the top of `SKILL.md`.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Frontmatter {
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub description: Option<String>,
+    // `version: 1.0` is a YAML number; keep it as a string.
+    #[serde(default, deserialize_with = "scalar_string")]
+    pub version: Option<String>,
+    #[serde(default)]
+    pub license: Option<String>,
+    /// `allowed-tools`, `metadata`, and anything else agents define.
+    #[serde(flatten)]
+    pub extra: BTreeMap<String, Value>,
+}
+
+impl Frontmatter {
+    /// The description collapsed onto one line, for lists.
+    pub fn summary(&self) -> Option<String> {
+        let description = self.description.as_deref()?;
+        let summary = description.split_whitespace().collect::<Vec<_>>().join(" ");
+        (!summary.is_empty()).then_some(summary)
+    }
+}
+
+/// Parses the frontmatter of a `SKILL.md`; `None` if the file has none.
+pub fn parse(content: &str) -> Result<Option<Frontmatter>> {
+    let content = content.strip_prefix('\u{feff}').unwrap_or(content);
+    let mut lines = content.lines();
+    if lines.next().map(str::trim_end) != Some("---") {
+        return Ok(None);
+    }
+    let mut yaml = String::new();
+    for line in lines {
+        if matches!(line.trim_end(), "---" | "...") {
+            if yaml.trim().is_empty() {
+                return Ok(Some(Frontmatter::default()));
+            }
+            let frontmatter = serde_norway::from_str(&yaml).context("Invalid frontmatter")?;
+            return Ok(Some(frontmatter));
+        }
+        yaml.push_str(line);
+        yaml.push('\n');
+    }
+    Err(anyhow!("Frontmatter is not closed with ---"))
+}
+
+/// Reads the frontmatter of the skill in `skill_dir`.
+pub fn read(skill_dir: &Path) -> Result<Option<Frontmatter>> {
+    let path = skill_dir.join(SKILL_FILE);
+    let content =
+        fs::read_to_string(&path).with_context(|| format!("Failed to read {}", path.display()))?;
+    parse(&content).with_context(|| format!("Failed to parse {}", path.display()))
+}
+
+/// One-line description of the skill in `skill_dir`, if it has a readable one.
+pub fn description(skill_dir: &Path) -> Option<String> {
+    match read(skill_dir) {
+        Ok(frontmatter) => frontmatter?.summary(),
+        Err(err) => {
+            log::info!("{:#}", err);
+            None
+        }
+    }
+}
+
+fn scalar_string<'de, D>(deserializer: D) -> Result<Option<String>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    Ok(match Option::<Value>::deserialize(deserializer)? {
+        Some(Value::String(value)) => Some(value),
+        Some(Value::Number(value)) => Some(value.to_string()),
+        Some(Value::Bool(value)) => Some(value.to_string()),
+        _ => None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_frontmatter() {
+        let content = "---\r\nname: lint\r\ndescription: >\r\n  Runs linters\r\n  on changed files.\r\nversion: 1.0\r\nlicense: MIT\r\nallowed-tools: [Bash]\r\n---\r\n# Lint\r\n";
+        let frontmatter = parse(content).unwrap().unwrap();
+
+        assert_eq!(frontmatter.name.as_deref(), Some("lint"));
+        assert_eq!(
+            frontmatter.summary().as_deref(),
+            Some("Runs linters on changed files.")
+        );
+        assert_eq!(frontmatter.version.as_deref(), Some("1.0"));
+        assert_eq!(frontmatter.license.as_deref(), Some("MIT"));
+        assert!(frontmatter.extra.contains_key("allowed-tools"));
+    }
+
+    #[test]
+    fn test_parse_without_or_with_broken_frontmatter() {
+        assert!(parse("# Lint\n\nNo frontmatter here.\n").unwrap().is_none());
+        assert!(parse("---\n---\nbody").unwrap().unwrap().name.is_none());
+        assert!(parse("---\nname: lint\n").is_err());
+        assert!(parse("---\nname: [lint\n---\n").is_err());
+        assert!(parse("---\nname: lint\nname: other\n---\n").is_err());
+        assert!(parse("---\njust text\n---\n").is_err());
+        assert!(parse("---\ndescription: \"unterminated\n---\n").is_err());
+    }
+
+    #[test]
+    fn test_parse_frontmatter_value_styles() {
+        let content = concat!(
+            "---\n",
+            "# A comment\n",
+            "\"name\": 'it''s'  # trailing comment\n",
+            "description: |+\n",
+            "  First line.\n",
+            "    Indented.\n",
+            "\n",
+            "\n",
+            "allowed-tools:\n",
+            "- Bash\n",
+            "- Read\n",
+            "metadata: {owner: team}\n",
+            "version: \"1.10\"\n",
+            "license:\n",
+            "---\n",
+        );
+        let frontmatter = parse(content).unwrap().unwrap();
+
+        assert_eq!(frontmatter.name.as_deref(), Some("it's"));
+        assert_eq!(
+            frontmatter.description.as_deref(),
+            Some("First line.\n  Indented.\n\n\n")
+        );
+        assert_eq!(frontmatter.version.as_deref(), Some("1.10"));
+        assert_eq!(frontmatter.license, None);
+        assert_eq!(frontmatter.extra["allowed-tools"][1], "Read");
+        assert_eq!(frontmatter.extra["metadata"]["owner"], "team");
+
+        let frontmatter = parse(concat!(
+            "---\n",
+            "description: Runs\n  linters.\n",
+            "name: \"a\\tb\\x41\\u00e9\\\n  c\"\n",
+            "---\n",
+        ))
+        .unwrap()
+        .unwrap();
+        assert_eq!(frontmatter.description.as_deref(), Some("Runs linters."));
+        assert_eq!(frontmatter.name.as_deref(), Some("a\tbA\u{e9}c"));
+        assert!(parse("---\nname: [lint]\n---\n").is_err());
+    }
+}
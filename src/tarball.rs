@@ -0,0 +1,375 @@
+use anyhow::{anyhow, Context, Result};
+use flate2::read::GzDecoder;
+use std::fs;
+use std::io::{Read, Write};
+use std::path::{Component, Path, PathBuf};
+
+/// What was unpacked from an archive.
+pub struct Extracted {
+    /// Directory corresponding to the repository root.
+    pub root: PathBuf,
+    /// Commit recorded in the archive's pax header, as written by `git archive`.
+    pub commit: Option<String>,
+}
+
+/// How an archive maps onto a repository checkout.
+pub enum Layout<'a> {
+    /// GitHub archives: one `<repo>-<ref>/` top-level directory. Only `subpath`
+    /// and `.claude-plugin/` are extracted when a subpath is given.
+    GitHub { subpath: Option<&'a str> },
+    /// Any other `.tar.gz`; a single top-level directory is treated as the root.
+    Plain,
+}
+
+/// The codeload URL serving `git_url` at `git_ref` (default branch if `None`).
+pub fn github_archive_url(git_url: &str, git_ref: Option<&str>) -> Option<String> {
+    let path = git_url.strip_prefix("https://github.com/")?;
+    let path = path.trim_end_matches('/');
+    let path = path.strip_suffix(".git").unwrap_or(path);
+    let (owner, repo) = path.split_once('/')?;
+    if owner.is_empty() || repo.is_empty() || repo.contains('/') {
+        return None;
+    }
+    Some(format!(
+        "https://codeload.github.com/{}/{}/tar.gz/{}",
+        owner,
+        repo,
+        git_ref.unwrap_or("HEAD")
+    ))
+}
+
+pub fn is_tarball_url(url: &str) -> bool {
+    (url.starts_with("http://") || url.starts_with("https://"))
+        && (url.ends_with(".tar.gz") || url.ends_with(".tgz"))
+}
+
+/// Streams a `.tar.gz` over HTTP and unpacks it into `dest`.
+pub fn download(archive_url: &str, dest: &Path, layout: Layout) -> Result<Extracted> {
+    let resp = get(archive_url)?;
+    extract(resp, dest, layout).with_context(|| format!("Failed to unpack {}", archive_url))
+}
+
+/// Downloads a `.tar.gz` as is, to be unpacked later with [`extract`].
+pub fn save(archive_url: &str, dest: &mut impl Write) -> Result<()> {
+    let mut resp = get(archive_url)?;
+    resp.copy_to(dest)
+        .with_context(|| format!("Failed to download {}", archive_url))?;
+    Ok(())
+}
+
+fn get(archive_url: &str) -> Result<reqwest::blocking::Response> {
+    let resp = reqwest::blocking::get(archive_url)
+        .with_context(|| format!("Failed to download {}", archive_url))?;
+    if !resp.status().is_success() {
+        return Err(anyhow!(
+            "Failed to download {}: status {}",
+            archive_url,
+            resp.status()
+        ));
+    }
+    Ok(resp)
+}
+
+pub fn extract(reader: impl Read, dest: &Path, layout: Layout) -> Result<Extracted> {
+    let (strip_root, subpath) = match layout {
+        Layout::GitHub { subpath } => (true, subpath.and_then(normalize_subpath)),
+        Layout::Plain => (false, None),
+    };
+    let mut archive = tar::Archive::new(GzDecoder::new(reader));
+    let mut commit = None;
+    let mut links = Vec::new();
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        let entry_type = entry.header().entry_type();
+        if entry_type.is_pax_global_extensions() {
+            if let Some(extensions) = entry.pax_extensions()? {
+                for extension in extensions {
+                    let extension = extension?;
+                    if extension.key() == Ok("comment") {
+                        commit = extension.value().ok().map(|value| value.trim().to_string());
+                    }
+                }
+            }
+            continue;
+        }
+        let is_link = entry_type.is_symlink() || entry_type.is_hard_link();
+        if !(entry_type.is_file() || entry_type.is_dir() || is_link) {
+            continue;
+        }
+        let path = entry.path()?.into_owned();
+        let Some(relative) = safe_relative_path(&path, strip_root) else {
+            continue;
+        };
+        if let Some(subpath) = &subpath
+            && !relative.starts_with(subpath)
+            && !relative.starts_with(".claude-plugin")
+        {
+            continue;
+        }
+        if beyond_symlink(dest, &relative) {
+            return Err(anyhow!("{} is beyond a symbolic link", path.display()));
+        }
+        let target = dest.join(&relative);
+        if entry_type.is_dir() {
+            fs::create_dir_all(&target)?;
+            continue;
+        }
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        if entry_type.is_hard_link() {
+            // Git has no hard links; a copy of the linked file is what a checkout holds.
+            let source = entry
+                .link_name()?
+                .and_then(|name| safe_relative_path(&name, strip_root))
+                .filter(|source| !beyond_symlink(dest, source))
+                .map(|source| dest.join(source))
+                .filter(|source| fs::symlink_metadata(source).is_ok_and(|meta| meta.is_file()))
+                .ok_or_else(|| anyhow!("{} links to a file outside the archive", path.display()))?;
+            fs::copy(source, &target)?;
+            continue;
+        }
+        entry.unpack(&target)?;
+        if entry_type.is_symlink() {
+            links.push(relative);
+        }
+    }
+
+    // Checked once everything is unpacked, since links may point at later entries.
+    let canonical_dest = fs::canonicalize(dest)?;
+    for link in &links {
+        let resolved = fs::canonicalize(dest.join(link));
+        if !matches!(&resolved, Ok(resolved) if resolved.starts_with(&canonical_dest)) {
+            return Err(anyhow!(
+                "{} is a symbolic link to {}, outside the extracted files",
+                link.display(),
+                fs::read_link(dest.join(link))?.display()
+            ));
+        }
+    }
+
+    let root = if strip_root { dest.to_path_buf() } else { single_top_dir(dest)? };
+    Ok(Extracted { root, commit })
+}
+
+/// Whether a parent directory of `relative` is a symbolic link, which an entry
+/// must not be written through.
+fn beyond_symlink(dest: &Path, relative: &Path) -> bool {
+    relative
+        .ancestors()
+        .skip(1)
+        .filter(|ancestor| !ancestor.as_os_str().is_empty())
+        .any(|ancestor| dest.join(ancestor).is_symlink())
+}
+
+/// Drops the archive's top-level directory if asked and rejects paths escaping the root.
+fn safe_relative_path(path: &Path, strip_root: bool) -> Option<PathBuf> {
+    let mut components = path.components();
+    if strip_root {
+        components.next()?;
+    }
+    let mut relative = PathBuf::new();
+    for component in components {
+        match component {
+            Component::Normal(part) => relative.push(part),
+            Component::CurDir => {}
+            _ => return None,
+        }
+    }
+    if relative.as_os_str().is_empty() {
+        None
+    } else {
+        Some(relative)
+    }
+}
+
+fn normalize_subpath(subpath: &str) -> Option<PathBuf> {
+    let path = Path::new(subpath);
+    // `../` paths leave the repository; extract everything and let the caller report it.
+    if path
+        .components()
+        .any(|component| !matches!(component, Component::Normal(_) | Component::CurDir))
+    {
+        return None;
+    }
+    let normalized: PathBuf = path
+        .components()
+        .filter(|component| matches!(component, Component::Normal(_)))
+        .collect();
+    if normalized.as_os_str().is_empty() {
+        None
+    } else {
+        Some(normalized)
+    }
+}
+
+fn single_top_dir(dest: &Path) -> Result<PathBuf> {
+    let entries = fs::read_dir(dest)?.collect::<std::io::Result<Vec<_>>>()?;
+    if let [entry] = entries.as_slice()
+        && entry.file_type()?.is_dir()
+    {
+        return Ok(entry.path());
+    }
+    Ok(dest.to_path_buf())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::write::GzEncoder;
+    use flate2::Compression;
+
+    fn archive(files: &[(&str, &str)], commit: Option<&str>) -> Vec<u8> {
+        let mut builder = tar::Builder::new(GzEncoder::new(Vec::new(), Compression::fast()));
+        if let Some(commit) = commit {
+            let record = format!("{} comment={}\n", 9 + commit.len() + 3, commit);
+            let mut header = tar::Header::new_ustar();
+            header.set_entry_type(tar::EntryType::XGlobalHeader);
+            header.set_size(record.len() as u64);
+            header.set_cksum();
+            builder
+                .append_data(&mut header, "pax_global_header", record.as_bytes())
+                .unwrap();
+        }
+        for (path, content) in files {
+            let mut header = tar::Header::new_old();
+            // Written by hand: `set_path` refuses the `..` entries we test against.
+            header.as_old_mut().name[..path.len()].copy_from_slice(path.as_bytes());
+            header.set_size(content.len() as u64);
+            header.set_mode(0o644);
+            header.set_cksum();
+            builder.append(&header, content.as_bytes()).unwrap();
+        }
+        builder.into_inner().unwrap().finish().unwrap()
+    }
+
+    fn archive_with_links(links: &[(&str, tar::EntryType, &str)]) -> Vec<u8> {
+        let mut builder = tar::Builder::new(GzEncoder::new(Vec::new(), Compression::fast()));
+        let mut header = tar::Header::new_gnu();
+        header.set_size(4);
+        header.set_mode(0o644);
+        header.set_cksum();
+        builder
+            .append_data(&mut header, "repo-main/skills/lint/SKILL.md", "lint".as_bytes())
+            .unwrap();
+        for (path, entry_type, link_name) in links {
+            let mut header = tar::Header::new_gnu();
+            header.set_entry_type(*entry_type);
+            header.set_size(0);
+            header.set_mode(0o777);
+            builder.append_link(&mut header, path, link_name).unwrap();
+        }
+        builder.into_inner().unwrap().finish().unwrap()
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_extract_links_inside_the_tree_only() {
+        let github = Layout::GitHub { subpath: None };
+        let data = archive_with_links(&[
+            ("repo-main/skills/lint/README.md", tar::EntryType::Symlink, "SKILL.md"),
+            (
+                "repo-main/skills/lint/COPY.md",
+                tar::EntryType::Link,
+                "repo-main/skills/lint/SKILL.md",
+            ),
+        ]);
+        let temp = tempfile::tempdir().unwrap();
+        let extracted = extract(data.as_slice(), temp.path(), github).unwrap();
+        let skill = extracted.root.join("skills/lint");
+        assert!(skill.join("README.md").is_symlink());
+        assert_eq!(fs::read_to_string(skill.join("README.md")).unwrap(), "lint");
+        assert_eq!(fs::read_to_string(skill.join("COPY.md")).unwrap(), "lint");
+
+        let escaping: [&[(&str, tar::EntryType, &str)]; 4] = [
+            &[("repo-main/skills/up", tar::EntryType::Symlink, "../..")],
+            &[("repo-main/skills/etc", tar::EntryType::Symlink, "/etc")],
+            &[("repo-main/skills/copy", tar::EntryType::Link, "/etc/hostname")],
+            &[
+                ("repo-main/skills/x", tar::EntryType::Symlink, "lint"),
+                ("repo-main/skills/x/y", tar::EntryType::Symlink, "SKILL.md"),
+            ],
+        ];
+        for links in escaping {
+            let data = archive_with_links(links);
+            let temp = tempfile::tempdir().unwrap();
+            let github = Layout::GitHub { subpath: None };
+            assert!(extract(data.as_slice(), temp.path(), github).is_err(), "{:?}", links);
+        }
+
+        // A link out of the requested subpath would dangle in the extracted tree.
+        let data = archive_with_links(&[(
+            "repo-main/plugins/tools/shared",
+            tar::EntryType::Symlink,
+            "../../skills",
+        )]);
+        let temp = tempfile::tempdir().unwrap();
+        let layout = Layout::GitHub {
+            subpath: Some("plugins/tools"),
+        };
+        assert!(extract(data.as_slice(), temp.path(), layout).is_err());
+    }
+
+    #[test]
+    fn test_github_archive_url() {
+        assert_eq!(
+            github_archive_url("https://github.com/owner/repo.git", Some("v1.0")).as_deref(),
+            Some("https://codeload.github.com/owner/repo/tar.gz/v1.0")
+        );
+        assert_eq!(
+            github_archive_url("https://github.com/owner/repo", None).as_deref(),
+            Some("https://codeload.github.com/owner/repo/tar.gz/HEAD")
+        );
+        assert!(github_archive_url("git@github.com:owner/repo.git", None).is_none());
+        assert!(github_archive_url("https://gitlab.com/owner/repo.git", None).is_none());
+    }
+
+    #[test]
+    fn test_extract_github_subpath_and_commit() {
+        let commit = "0123456789abcdef0123456789abcdef01234567";
+        let data = archive(
+            &[
+                ("repo-main/.claude-plugin/marketplace.json", "{}"),
+                ("repo-main/plugins/tools/skills/lint/SKILL.md", "lint"),
+                ("repo-main/plugins/other/skills/deploy/SKILL.md", "deploy"),
+                ("repo-main/README.md", "readme"),
+            ],
+            Some(commit),
+        );
+        let temp = tempfile::tempdir().unwrap();
+        let extracted = extract(
+            data.as_slice(),
+            temp.path(),
+            Layout::GitHub {
+                subpath: Some("./plugins/tools"),
+            },
+        )
+        .unwrap();
+
+        assert_eq!(extracted.root, temp.path());
+        assert_eq!(extracted.commit.as_deref(), Some(commit));
+        let root = &extracted.root;
+        assert!(root.join("plugins/tools/skills/lint/SKILL.md").is_file());
+        assert!(root.join(".claude-plugin/marketplace.json").is_file());
+        assert!(!root.join("plugins/other").exists());
+        assert!(!root.join("README.md").exists());
+    }
+
+    #[test]
+    fn test_extract_plain_archive_uses_single_top_dir() {
+        let data = archive(
+            &[
+                ("plugin-1.0/skills/lint/SKILL.md", "lint"),
+                ("../escape.txt", "nope"),
+            ],
+            None,
+        );
+        let temp = tempfile::tempdir().unwrap();
+        let extracted = extract(data.as_slice(), temp.path(), Layout::Plain).unwrap();
+
+        assert_eq!(extracted.root, temp.path().join("plugin-1.0"));
+        assert!(extracted.root.join("skills/lint/SKILL.md").is_file());
+        assert!(extracted.commit.is_none());
+        assert!(!temp.path().parent().unwrap().join("escape.txt").exists());
+    }
+}
@@ -0,0 +1,334 @@
+use crate::cli::Scope;
+use crate::config::Config;
+use anyhow::{anyhow, Result};
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+/// Name accepted by `--target` that expands to every registered target.
+pub const ALL: &str = "all";
+
+static REGISTRY: OnceLock<TargetRegistry> = OnceLock::new();
+
+/// An install target (agent) known to the registry, identified by name.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Target(String);
+
+impl Target {
+    pub fn name(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for Target {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+#[derive(Clone, Debug)]
+enum GlobalDir {
+    Home(PathBuf),
+    Absolute(PathBuf),
+}
+
+#[derive(Clone, Debug)]
+struct TargetDef {
+    target: Target,
+    project_dir: PathBuf,
+    global_dir: Option<GlobalDir>,
+}
+
+#[derive(Debug)]
+pub struct TargetRegistry {
+    defs: Vec<TargetDef>,
+}
+
+impl TargetRegistry {
+    pub fn builtin() -> Self {
+        let builtin = |name: &str, project_dir: &str, global_dir: &str| TargetDef {
+            target: Target(name.to_string()),
+            project_dir: PathBuf::from(project_dir),
+            global_dir: Some(GlobalDir::Home(PathBuf::from(global_dir))),
+        };
+        Self {
+            defs: vec![
+                builtin("codex", ".codex/skills", ".codex/skills"),
+                // Opencode reads XDG-style config paths on every platform.
+                builtin("opencode", ".opencode/skills", ".config/opencode/skills"),
+                builtin("antigravity", ".agent/skills", ".gemini/antigravity/skills"),
+                builtin("claude", ".claude/skills", ".claude/skills"),
+            ],
+        }
+    }
+
+    /// Built-in targets plus the ones declared in `config`; a config entry
+    /// with a built-in name replaces that target's directories.
+    pub fn with_config(config: &Config) -> Result<Self> {
+        let mut registry = Self::builtin();
+        for (name, target_config) in &config.targets {
+            if name == ALL || name.is_empty() {
+                return Err(anyhow!("Invalid target name in config: {:?}", name));
+            }
+            // `--target` is matched case-insensitively against lowercase names.
+            if name.chars().any(|c| c.is_ascii_uppercase()) {
+                return Err(anyhow!(
+                    "Target name {:?} in config must be lowercase, e.g. {:?}",
+                    name,
+                    name.to_ascii_lowercase()
+                ));
+            }
+            let def = TargetDef {
+                target: Target(name.clone()),
+                project_dir: PathBuf::from(&target_config.dir),
+                global_dir: target_config
+                    .global_dir
+                    .as_deref()
+                    .map(|dir| parse_global_dir(name, dir))
+                    .transpose()?,
+            };
+            match registry.defs.iter_mut().find(|existing| existing.target.0 == *name) {
+                Some(existing) => *existing = def,
+                None => registry.defs.push(def),
+            }
+        }
+        Ok(registry)
+    }
+
+    /// Every registered target, built-ins first.
+    pub fn all(&self) -> Vec<Target> {
+        self.defs.iter().map(|def| def.target.clone()).collect()
+    }
+
+    /// Targets that have a skills directory in `scope`.
+    pub fn all_in(&self, scope: Scope) -> Vec<Target> {
+        self.defs
+            .iter()
+            .filter(|def| scope == Scope::Project || def.global_dir.is_some())
+            .map(|def| def.target.clone())
+            .collect()
+    }
+
+    pub fn supports(&self, target: &Target, scope: Scope) -> bool {
+        self.all_in(scope).contains(target)
+    }
+
+    pub fn parse(&self, name: &str) -> Result<Target> {
+        let name = name.to_ascii_lowercase();
+        self.defs
+            .iter()
+            .find(|def| def.target.0 == name)
+            .map(|def| def.target.clone())
+            .ok_or_else(|| {
+                anyhow!(
+                    "Unknown target: {}. Known targets: {}, {}",
+                    name,
+                    self.all()
+                        .iter()
+                        .map(|target| target.name())
+                        .collect::<Vec<_>>()
+                        .join(", "),
+                    ALL
+                )
+            })
+    }
+
+    /// Parses target names, expanding `all` and dropping duplicates.
+    pub fn resolve(&self, names: &[String]) -> Result<Vec<Target>> {
+        if names.iter().any(|name| name.eq_ignore_ascii_case(ALL)) {
+            return Ok(self.all());
+        }
+        let mut targets = Vec::new();
+        for name in names {
+            let target = self.parse(name)?;
+            if !targets.contains(&target) {
+                targets.push(target);
+            }
+        }
+        Ok(targets)
+    }
+
+    pub fn skills_dir(&self, target: &Target, scope: Scope, project_root: &Path) -> Result<PathBuf> {
+        let def = self
+            .defs
+            .iter()
+            .find(|def| def.target == *target)
+            .ok_or_else(|| anyhow!("Unknown target: {}", target))?;
+        match scope {
+            Scope::Project => Ok(project_root.join(&def.project_dir)),
+            Scope::Global => match &def.global_dir {
+                Some(GlobalDir::Absolute(dir)) => Ok(dir.clone()),
+                Some(GlobalDir::Home(relative)) => {
+                    let base_dirs = directories::BaseDirs::new().ok_or_else(|| {
+                        anyhow!("Could not determine the home directory for global skills")
+                    })?;
+                    Ok(base_dirs.home_dir().join(relative))
+                }
+                None => Err(anyhow!("Target {} has no global_dir configured", target)),
+            },
+        }
+    }
+}
+
+/// `~`, `~/...`, or an absolute path; anything else would depend on the directory
+/// skop runs in.
+fn parse_global_dir(name: &str, dir: &str) -> Result<GlobalDir> {
+    if dir == "~" {
+        return Ok(GlobalDir::Home(PathBuf::new()));
+    }
+    if let Some(relative) = dir.strip_prefix("~/") {
+        return Ok(GlobalDir::Home(PathBuf::from(relative)));
+    }
+    if Path::new(dir).has_root() {
+        return Ok(GlobalDir::Absolute(PathBuf::from(dir)));
+    }
+    Err(anyhow!(
+        "global_dir {:?} of target {} in config must be absolute or start with ~/",
+        dir,
+        name
+    ))
+}
+
+/// Loads the registry from the merged user and project config. Call once at startup.
+pub fn init_registry(config: &Config) -> Result<()> {
+    let registry = TargetRegistry::with_config(config)?;
+    let _ = REGISTRY.set(registry);
+    Ok(())
+}
+
+/// The registry loaded by [`init_registry`], or the built-in targets if it was never called.
+pub fn registry() -> &'static TargetRegistry {
+    REGISTRY.get_or_init(TargetRegistry::builtin)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::TargetConfig;
+
+    fn names(targets: &[Target]) -> Vec<&str> {
+        targets.iter().map(|target| target.name()).collect()
+    }
+
+    #[test]
+    fn test_resolve_expands_all_and_dedupes() {
+        let registry = TargetRegistry::builtin();
+        assert_eq!(
+            names(&registry.resolve(&["opencode".to_string(), "all".to_string()]).unwrap()),
+            ["codex", "opencode", "antigravity", "claude"]
+        );
+        assert_eq!(
+            names(
+                &registry
+                    .resolve(&[
+                        "opencode".to_string(),
+                        "Codex".to_string(),
+                        "opencode".to_string()
+                    ])
+                    .unwrap()
+            ),
+            ["opencode", "codex"]
+        );
+        let err = registry.resolve(&["vim".to_string()]).unwrap_err().to_string();
+        assert!(err.contains("Known targets: codex, opencode, antigravity, claude, all"));
+    }
+
+    #[test]
+    fn test_config_adds_and_overrides_targets() {
+        let mut config = Config::default();
+        config.targets.insert(
+            "internal".to_string(),
+            TargetConfig {
+                dir: ".internal/skills".to_string(),
+                global_dir: None,
+            },
+        );
+        config.targets.insert(
+            "codex".to_string(),
+            TargetConfig {
+                dir: ".codex-fork/skills".to_string(),
+                global_dir: Some("/opt/codex/skills".to_string()),
+            },
+        );
+        let registry = TargetRegistry::with_config(&config).unwrap();
+        let root = Path::new("/work/project");
+
+        assert_eq!(
+            names(&registry.all()),
+            ["codex", "opencode", "antigravity", "claude", "internal"]
+        );
+        let internal = registry.parse("internal").unwrap();
+        assert_eq!(
+            registry.skills_dir(&internal, Scope::Project, root).unwrap(),
+            root.join(".internal/skills")
+        );
+        assert!(registry.skills_dir(&internal, Scope::Global, root).is_err());
+        assert!(!registry.all_in(Scope::Global).contains(&internal));
+
+        let codex = registry.parse("codex").unwrap();
+        assert_eq!(
+            registry.skills_dir(&codex, Scope::Global, root).unwrap(),
+            PathBuf::from("/opt/codex/skills")
+        );
+    }
+
+    #[test]
+    fn test_config_rejects_reserved_name() {
+        let mut config = Config::default();
+        config.targets.insert(
+            ALL.to_string(),
+            TargetConfig {
+                dir: ".all/skills".to_string(),
+                global_dir: None,
+            },
+        );
+        assert!(TargetRegistry::with_config(&config).is_err());
+    }
+
+    #[test]
+    fn test_config_rejects_uppercase_name() {
+        let mut config = Config::default();
+        config.targets.insert(
+            "MyAgent".to_string(),
+            TargetConfig {
+                dir: ".myagent/skills".to_string(),
+                global_dir: None,
+            },
+        );
+        let err = TargetRegistry::with_config(&config).unwrap_err().to_string();
+        assert!(err.contains("must be lowercase"));
+    }
+
+    #[test]
+    fn test_config_global_dir_must_be_home_or_absolute() {
+        let registry = |global_dir: &str| {
+            let mut config = Config::default();
+            config.targets.insert(
+                "my-agent".to_string(),
+                TargetConfig {
+                    dir: ".my-agent/skills".to_string(),
+                    global_dir: Some(global_dir.to_string()),
+                },
+            );
+            TargetRegistry::with_config(&config)
+        };
+        let root = Path::new("/work/project");
+        let target = Target("my-agent".to_string());
+        let home = directories::BaseDirs::new().unwrap().home_dir().to_path_buf();
+
+        let global = |dir: &str| {
+            registry(dir)
+                .unwrap()
+                .skills_dir(&target, Scope::Global, root)
+                .unwrap()
+        };
+        assert_eq!(global("~"), home);
+        assert_eq!(global("~/.my-agent/skills"), home.join(".my-agent/skills"));
+        assert_eq!(global("/opt/skills"), PathBuf::from("/opt/skills"));
+
+        for dir in [".agents/skills", "~user/skills", "skills"] {
+            let err = registry(dir).unwrap_err().to_string();
+            assert!(err.contains("global_dir") && err.contains("my-agent"), "{}", err);
+        }
+    }
+}
@@ -1,26 +1,224 @@
-use crate::cli::Target;
+use crate::cli::Scope;
+use crate::targets::{self, Target};
+use anyhow::{Context, Result};
 use std::env;
-use std::path::PathBuf;
+use std::fs;
+use std::path::{Path, PathBuf};
 
-pub fn get_skills_dir(target: Target) -> PathBuf {
-    let current_dir = env::current_dir().unwrap_or_else(|_| PathBuf::from("."));
+pub fn project_root() -> PathBuf {
+    env::current_dir().unwrap_or_else(|_| PathBuf::from("."))
+}
+
+pub fn get_skills_dir(target: &Target, scope: Scope) -> Result<PathBuf> {
+    targets::registry().skills_dir(target, scope, &project_root())
+}
+
+/// Whether `target`'s project skills directory is its global one, as when skop
+/// runs from the home directory.
+pub fn project_dir_is_global(target: &Target) -> Result<bool> {
+    if !targets::registry().supports(target, Scope::Global) {
+        return Ok(false);
+    }
+    Ok(same_path(
+        &get_skills_dir(target, Scope::Project)?,
+        &get_skills_dir(target, Scope::Global)?,
+    ))
+}
+
+fn same_path(a: &Path, b: &Path) -> bool {
+    match (fs::canonicalize(a), fs::canonicalize(b)) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => a == b,
+    }
+}
+
+/// Whether `repo` is a URL (`<scheme>://...`) or an scp-style `user@host:path`,
+/// rather than GitHub `owner/repo` shorthand.
+pub fn is_git_url(repo: &str) -> bool {
+    if let Some((scheme, _)) = repo.split_once("://") {
+        return scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+            && scheme
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'));
+    }
+    match repo.split_once(':') {
+        Some((user_host, path)) if !path.is_empty() && !user_host.contains('/') => {
+            matches!(user_host.split_once('@'), Some((user, host)) if !user.is_empty() && !host.is_empty())
+        }
+        _ => false,
+    }
+}
+
+/// The directory of a marketplace given as a local path or `file://` URL.
+pub fn local_marketplace_path(spec: &str) -> Option<PathBuf> {
+    if let Some(path) = spec.strip_prefix("file://") {
+        return Some(PathBuf::from(path));
+    }
+    if spec.starts_with('.') || Path::new(spec).is_absolute() {
+        return Some(PathBuf::from(spec));
+    }
+    None
+}
+
+/// A local `spec` as it should be written to project files: relative to the
+/// project root (`./marketplace`, `../shared`), so the files work in any checkout.
+/// Other specs are returned unchanged.
+pub fn portable_local_spec(spec: &str) -> Result<String> {
+    let Some(dir) = local_marketplace_path(spec) else {
+        return Ok(spec.to_string());
+    };
+    let dir = canonical_dir(&dir)?;
+    let root = fs::canonicalize(project_root()).unwrap_or_else(|_| project_root());
+    Ok(relative_path(&dir, &root).unwrap_or_else(|| dir.to_string_lossy().to_string()))
+}
+
+/// A local `spec` as an absolute path, for looking it up and as a cache key.
+/// Other specs are returned unchanged.
+pub fn absolute_local_spec(spec: &str) -> Result<String> {
+    match local_marketplace_path(spec) {
+        Some(dir) => Ok(canonical_dir(&dir)?.to_string_lossy().to_string()),
+        None => Ok(spec.to_string()),
+    }
+}
+
+fn canonical_dir(dir: &Path) -> Result<PathBuf> {
+    fs::canonicalize(dir).with_context(|| format!("Failed to open {}", dir.display()))
+}
+
+/// `path` relative to `base`, both absolute; `None` if they share no root.
+fn relative_path(path: &Path, base: &Path) -> Option<String> {
+    let path: Vec<_> = path.components().collect();
+    let base: Vec<_> = base.components().collect();
+    let common = path.iter().zip(&base).take_while(|(a, b)| a == b).count();
+    if common == 0 {
+        return None;
+    }
+    let mut parts: Vec<String> = vec!["..".to_string(); base.len() - common];
+    if parts.is_empty() {
+        parts.push(".".to_string());
+    }
+    parts.extend(
+        path[common..]
+            .iter()
+            .map(|component| component.as_os_str().to_string_lossy().to_string()),
+    );
+    Some(parts.join("/"))
+}
 
-    match target {
-        Target::Codex => current_dir.join(".codex/skills"),
-        Target::Opencode => current_dir.join(".opencode/skills"),
-        Target::Antigravity => current_dir.join(".agent/skills"),
-        Target::All => unreachable!("Target::All should be handled before resolving a skills dir"),
+/// Splits a marketplace spec like `owner/repo@v1.2.0` into the repo and the pinned ref.
+/// Git URLs pin with `#ref` instead, since `git@host:...` already contains an `@`.
+pub fn split_marketplace_ref(spec: &str) -> (&str, Option<&str>) {
+    if local_marketplace_path(spec).is_some() {
+        return (spec, None);
+    }
+    let separator = if is_git_url(spec) { '#' } else { '@' };
+    match spec.rsplit_once(separator) {
+        Some((repo, git_ref)) if !git_ref.is_empty() => (repo, Some(git_ref)),
+        _ => (spec, None),
     }
 }
 
-pub fn get_marketplace_url(repo: &str) -> String {
-    // Assuming github.com and main branch for now, as per typical conventions unless specified otherwise
-    // Real implementation might need to be smarter about branches (main/master) or use API.
-    // Spec says: "repo": "owner/repo" in marketplace.json for github source.
-    // For the marketplace file itself:
+pub fn join_marketplace_ref(repo: &str, git_ref: &str) -> String {
+    let separator = if is_git_url(repo) { '#' } else { '@' };
+    format!("{}{}{}", repo, separator, git_ref)
+}
+
+pub fn get_marketplace_url(repo: &str, git_ref: &str) -> String {
     // "Users add your marketplace with /plugin marketplace add owner/repo" -> implicitly looks for .claude-plugin/marketplace.json
     format!(
-        "https://raw.githubusercontent.com/{}/main/.claude-plugin/marketplace.json",
-        repo
+        "https://raw.githubusercontent.com/{}/{}/.claude-plugin/marketplace.json",
+        repo, git_ref
     )
 }
+
+pub fn get_lockfile_path() -> PathBuf {
+    project_root().join(crate::lockfile::LOCKFILE_NAME)
+}
+
+pub fn get_manifest_path() -> PathBuf {
+    project_root().join(crate::manifest::MANIFEST_NAME)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_marketplace_ref() {
+        assert_eq!(split_marketplace_ref("owner/repo"), ("owner/repo", None));
+        assert_eq!(
+            split_marketplace_ref("owner/repo@v1.2.0"),
+            ("owner/repo", Some("v1.2.0"))
+        );
+        assert_eq!(split_marketplace_ref("owner/repo@"), ("owner/repo@", None));
+        assert_eq!(
+            split_marketplace_ref("git@github.com:owner/repo.git"),
+            ("git@github.com:owner/repo.git", None)
+        );
+        assert_eq!(
+            split_marketplace_ref("https://gitlab.com/owner/repo.git#release"),
+            ("https://gitlab.com/owner/repo.git", Some("release"))
+        );
+        assert_eq!(split_marketplace_ref("./my@marketplace"), ("./my@marketplace", None));
+        assert_eq!(
+            split_marketplace_ref("ssh://git@host.example/team/skills.git"),
+            ("ssh://git@host.example/team/skills.git", None)
+        );
+        assert_eq!(
+            split_marketplace_ref("ssh://git@host.example/team/skills.git#v1"),
+            ("ssh://git@host.example/team/skills.git", Some("v1"))
+        );
+        assert_eq!(
+            split_marketplace_ref("git://host.example/skills.git#main"),
+            ("git://host.example/skills.git", Some("main"))
+        );
+        assert_eq!(
+            split_marketplace_ref("httpie/skills@v2"),
+            ("httpie/skills", Some("v2"))
+        );
+        assert_eq!(
+            join_marketplace_ref("https://gitlab.com/owner/repo.git", "release"),
+            "https://gitlab.com/owner/repo.git#release"
+        );
+    }
+
+    #[test]
+    fn test_relative_path() {
+        let base = Path::new("/work/project");
+        let relative = |path: &str| relative_path(Path::new(path), base);
+        assert_eq!(relative("/work/project").as_deref(), Some("."));
+        assert_eq!(relative("/work/project/marketplace").as_deref(), Some("./marketplace"));
+        assert_eq!(relative("/work/shared/skills").as_deref(), Some("../shared/skills"));
+        assert_eq!(relative("/other").as_deref(), Some("../../other"));
+    }
+
+    #[test]
+    fn test_is_git_url() {
+        assert!(is_git_url("https://gitlab.com/owner/repo.git"));
+        assert!(is_git_url("ssh://git@host.example/team/skills.git"));
+        assert!(is_git_url("git://host.example/skills.git"));
+        assert!(is_git_url("git+ssh://host.example/skills.git"));
+        assert!(is_git_url("git@github.com:owner/repo.git"));
+        assert!(is_git_url("deploy@host.example:skills.git"));
+        assert!(!is_git_url("httpie/skills"));
+        assert!(!is_git_url("httpie/skills@v2"));
+        assert!(!is_git_url("git-tools/skills@release:2"));
+        assert!(!is_git_url("owner/repo"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_same_path_follows_symlinks() {
+        let temp = tempfile::tempdir().unwrap();
+        let home = temp.path().join("home");
+        fs::create_dir_all(home.join(".codex/skills")).unwrap();
+        std::os::unix::fs::symlink(&home, temp.path().join("link")).unwrap();
+
+        assert!(same_path(
+            &home.join(".codex/skills"),
+            &temp.path().join("link/.codex/skills")
+        ));
+        assert!(same_path(&home.join("missing"), &home.join("missing")));
+        assert!(!same_path(&home.join(".codex/skills"), &home.join(".codex")));
+    }
+}